@@ -1,9 +1,10 @@
 extern crate keystring_generator;
 
-use std::path::PathBuf;
-use keystring_generator::generate;
+use keystring_generator::Config;
 
 fn main() {
-    let input_path = PathBuf::new().join("examples/simple.keys");
-    generate(&input_path).unwrap();
-}
\ No newline at end of file
+    Config::new()
+        .input("examples/simple.keys")
+        .generate()
+        .unwrap();
+}
@@ -0,0 +1,35 @@
+use keystring_generator::{Config, KeyTable};
+
+fn main() {
+    Config::new()
+        .input("keys/placeholders.keys")
+        .output_file_name("placeholders.rs")
+        .generate()
+        .unwrap();
+    Config::new()
+        .input("keys/enums.keys")
+        .output_file_name("enums.rs")
+        .key_enums(true)
+        .root_module("keys")
+        .generate()
+        .unwrap();
+    Config::new()
+        .input("keys/table.keys")
+        .output_file_name("table.rs")
+        .key_table(KeyTable::LeavesAndBases)
+        .generate()
+        .unwrap();
+    Config::new()
+        .input("keys/lookup.keys")
+        .output_file_name("lookup.rs")
+        .key_lookup(true)
+        .generate()
+        .unwrap();
+    Config::new()
+        .input("keys/typed.keys")
+        .output_file_name("typed.rs")
+        .typed_keys(true)
+        .key_enums(true)
+        .generate()
+        .unwrap();
+}
@@ -0,0 +1 @@
+//! Test crate compiling the code generated by `build.rs`, see `tests/generated.rs`.
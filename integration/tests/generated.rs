@@ -0,0 +1,73 @@
+//! The code generated by `build.rs` is compiled here, so generated items colliding with names from the key files are caught.
+
+mod placeholders {
+    include!(concat!(env!("OUT_DIR"), "/placeholders.rs"));
+}
+
+mod enums {
+    include!(concat!(env!("OUT_DIR"), "/enums.rs"));
+}
+
+mod table {
+    include!(concat!(env!("OUT_DIR"), "/table.rs"));
+}
+
+mod lookup {
+    include!(concat!(env!("OUT_DIR"), "/lookup.rs"));
+}
+
+mod typed {
+    include!(concat!(env!("OUT_DIR"), "/typed.rs"));
+}
+
+#[test]
+fn placeholder_parameters_do_not_collide_with_constants() {
+    assert_eq!("user.42.profile", placeholders::user::profile(42));
+    assert_eq!("user.id", placeholders::user::id);
+    assert_eq!("String.a", placeholders::String::a);
+    assert_eq!("7", placeholders::x(7));
+}
+
+#[test]
+fn key_enum_bindings_do_not_collide_with_constants() {
+    use enums::keys::{server, Key};
+
+    assert_eq!("server.key", server::Key::Key.as_str());
+    assert_eq!(Ok(Key::Server(server::Key::Tls(server::tls::Key::Cert))), "server.tls.cert".parse());
+    assert_eq!(Ok(server::Key::S), server::Key::try_from("server.s"));
+    assert_eq!("f", Key::F.to_string());
+    assert_eq!(7, Key::ALL.len());
+}
+
+#[test]
+fn key_table_is_searchable() {
+    assert_eq!(&["key", "server", "server.port", "server.user"], table::ALL_KEYS);
+    assert!(table::is_known_key("key"));
+    assert!(table::is_known_key("server.port"));
+    assert!(!table::is_known_key("server.user.1"));
+}
+
+#[test]
+fn key_metadata_is_looked_up() {
+    let info = lookup::lookup("server.port").unwrap();
+    assert_eq!(&["server", "port"], info.segments);
+    assert_eq!(Some("Port to listen on."), info.doc);
+    assert_eq!(Some("u16"), info.value_type);
+    assert_eq!(Some(3), info.line);
+    assert!(info.file.unwrap().ends_with("lookup.keys"));
+    assert_eq!("key", lookup::lookup("key").unwrap().key);
+    assert_eq!(None, lookup::lookup("server.host"));
+}
+
+#[test]
+fn typed_keys_behave_like_str() {
+    use std::collections::HashMap;
+
+    let mut values = HashMap::new();
+    values.insert(typed::server::port, 8080);
+    assert_eq!(Some(&8080), values.get("server.port"));
+    assert_eq!("Key", typed::Key.as_str());
+    assert_eq!("key", typed::key.to_string());
+    assert_eq!("server.tls.cert", &*typed::server::tls::cert);
+    assert_eq!("server.tls.cert", typed::server::Key::Tls(typed::server::tls::Key::Cert).as_str());
+}
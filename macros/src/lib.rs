@@ -0,0 +1,95 @@
+//! # Keystring generator macros
+//! Procedural macros to use the keystring generator without a build-script.
+
+use std::env;
+use std::path::PathBuf;
+
+use keystring_generator::Config;
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Expands to the code generated from the given input file.
+///
+/// The path is resolved relative to the directory of the `Cargo.toml` of the crate that invokes the macro.
+/// The format of the file is detected from its extension.
+/// The generated code is the same as the content of `keygen.rs` generated with the default [`Config`]:
+/// ```ignore
+/// mod constants {
+///     keystring_generator_macros::keys!("keys/config.keys");
+/// }
+///
+/// let port = constants::config::web::port;
+/// ```
+///
+/// Files referenced with `@include` are resolved relative to the including file.
+///
+/// If the file can not be read or parsed, the error is reported with `compile_error!`.
+#[proc_macro]
+pub fn keys(input: TokenStream) -> TokenStream {
+    let relative = match parse_path_literal(input) {
+        Ok(path) => path,
+        Err((message, span)) => return compile_error(&message, span),
+    };
+
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
+    let path = manifest_dir.join(relative);
+
+    match Config::new().input(&path).generate_to_string() {
+        Ok((code, files)) => {
+            // Including the files makes the compiler track them, so changes to the input trigger a recompilation.
+            let tracking = files.iter()
+                .map(|f| format!("const _: &[u8] = include_bytes!({:?});", f.display().to_string()))
+                .collect::<Vec<String>>()
+                .join("\n");
+            format!("{}\n{}", code, tracking)
+                .parse()
+                .unwrap_or_else(|_| compile_error("generated code could not be tokenized", Span::call_site()))
+        }
+        Err(e) => compile_error(&e.to_string(), Span::call_site()),
+    }
+}
+
+fn parse_path_literal(input: TokenStream) -> Result<String, (String, Span)> {
+    let mut tokens = input.into_iter();
+    let literal = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(literal)), None) => literal,
+        (Some(token), _) => return Err(("expected a single string literal".to_string(), token.span())),
+        (None, _) => return Err(("expected a string literal with the path of the input file".to_string(), Span::call_site())),
+    };
+
+    let text = literal.to_string();
+    if let Some(raw) = text.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let quoted = &raw[hashes..raw.len() - hashes];
+        if let Some(value) = quoted.strip_prefix('"').and_then(|q| q.strip_suffix('"')) {
+            return Ok(value.to_string());
+        }
+    } else if let Some(value) = text.strip_prefix('"').and_then(|q| q.strip_suffix('"')) {
+        return Ok(value.replace("\\\\", "\\").replace("\\\"", "\""));
+    }
+    Err(("expected a string literal".to_string(), literal.span()))
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    // The compiler already prefixes the message with `error: `.
+    let message = message.strip_prefix("error: ").unwrap_or(message);
+    let mut args = Literal::string(message);
+    args.set_span(span);
+    let mut group = Group::new(Delimiter::Parenthesis, TokenStream::from(TokenTree::Literal(args)));
+    group.set_span(span);
+
+    let tokens: Vec<TokenTree> = vec![
+        Ident::new("compile_error", span).into(),
+        {
+            let mut bang = Punct::new('!', Spacing::Alone);
+            bang.set_span(span);
+            bang.into()
+        },
+        group.into(),
+        {
+            let mut semicolon = Punct::new(';', Spacing::Alone);
+            semicolon.set_span(span);
+            semicolon.into()
+        },
+    ];
+    tokens.into_iter().collect()
+}
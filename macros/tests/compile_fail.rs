@@ -0,0 +1,4 @@
+#[test]
+fn errors_are_reported_with_compile_error() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
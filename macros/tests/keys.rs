@@ -0,0 +1,21 @@
+mod constants {
+    keystring_generator_macros::keys!("../src/test/hierarchical.keys");
+}
+
+#[test]
+fn keys_are_expanded_inline() {
+    assert_eq!("hierarchical.keys", constants::hierarchical::keys::_BASE);
+    assert_eq!("hierarchical.keys.with.six.hierarchical.layers", constants::hierarchical::keys::with::six::hierarchical::layers);
+}
+
+mod templates {
+    keystring_generator_macros::keys!("../src/test/placeholders.keys");
+}
+
+#[test]
+fn placeholders_generate_functions() {
+    assert_eq!("user.42.profile", templates::user::profile(42));
+    assert_eq!("tenant.acme.queue.jobs", templates::tenant::queue::name("acme", "jobs"));
+    assert_eq!("tenant.acme.settings", templates::tenant::settings::_BASE("acme"));
+    assert_eq!("tenant.acme.settings.theme", templates::tenant::settings::theme("acme"));
+}
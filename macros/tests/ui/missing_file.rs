@@ -0,0 +1,3 @@
+keystring_generator_macros::keys!("/nonexistent/missing.keys");
+
+fn main() {}
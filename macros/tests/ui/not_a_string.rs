@@ -0,0 +1,3 @@
+keystring_generator_macros::keys!(config);
+
+fn main() {}
@@ -0,0 +1,419 @@
+//! Configuration of the code generation.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Write};
+use std::ops::Not;
+use std::path::PathBuf;
+
+use crate::{generate_from_keys, glob, ArrayOfTables, Case, IdentifierPolicy, InputFormat, KeyElement, KeygenError};
+
+/// Visibility of the generated modules and constants.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    /// Items are declared `pub`.
+    Public,
+    /// Items are declared `pub(crate)`.
+    Crate,
+}
+
+impl Visibility {
+    pub(crate) fn keyword(&self) -> &'static str {
+        match self {
+            Visibility::Public => "pub",
+            Visibility::Crate => "pub(crate)",
+        }
+    }
+}
+
+/// Which keys are listed in the generated table `ALL_KEYS`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyTable {
+    /// Neither `ALL_KEYS` nor `is_known_key` are generated.
+    None,
+    /// The table contains the keys of all leaves.
+    Leaves,
+    /// The table contains the keys of all leaves and the `_BASE` keys of all modules.
+    LeavesAndBases,
+}
+
+/// Configuration of the generator.
+///
+/// The configuration is created with [`Config::new`] (or [`Default::default`]) and adjusted with chained setters.
+/// Calling [`Config::generate`] reads the input files and writes the generated code:
+/// ```no_run
+/// use keystring_generator::Config;
+///
+/// Config::new()
+///     .input("keys/config.keys")
+///     .output_dir("generated/keygen")
+///     .separator(":")
+///     .enable_warnings(true)
+///     .generate()
+///     .unwrap();
+/// ```
+///
+/// When running in a cargo build-script, the output is written to `$OUT_DIR/keygen.rs` by default and cargo is instructed
+/// to rerun the build-script if an input file changes. The generated code can then be included with
+/// [`include_keys!`](crate::include_keys).
+///
+/// When the code is generated with [`generate_from_str`](crate::generate_from_str), the options regarding files are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub(crate) inputs: Vec<PathBuf>,
+    pub(crate) input_globs: Vec<String>,
+    pub(crate) input_format: Option<InputFormat>,
+    pub(crate) output_dir: Option<PathBuf>,
+    pub(crate) output_file_name: String,
+    pub(crate) separator: String,
+    pub(crate) enable_warnings: bool,
+    pub(crate) root_module: Option<String>,
+    pub(crate) visibility: Visibility,
+    pub(crate) sort_keys: bool,
+    pub(crate) identifier_policy: IdentifierPolicy,
+    pub(crate) constant_case: Case,
+    pub(crate) module_case: Case,
+    pub(crate) toml_array_tables: ArrayOfTables,
+    pub(crate) split_env_names: bool,
+    pub(crate) fluent_terms: bool,
+    pub(crate) xml_name_separator: Option<char>,
+    pub(crate) key_enums: bool,
+    pub(crate) key_table: KeyTable,
+    pub(crate) key_lookup: bool,
+    pub(crate) typed_keys: bool,
+}
+
+impl Config {
+    /// Creates the default configuration.
+    ///
+    /// The defaults are:
+    ///  * no input files, their format is detected from the file extension
+    ///  * output to `$OUT_DIR/keygen.rs` in build-scripts, `generated/keygen/keygen.rs` otherwise
+    ///  * `.` as separator
+    ///  * warnings suppressed
+    ///  * no root module
+    ///  * `pub` visibility
+    ///  * keys in the order of the input
+    ///  * invalid identifiers are sanitized
+    ///  * identifiers of constants and modules are not converted
+    ///  * arrays of tables in TOML inputs are skipped
+    ///  * names in dotenv inputs are not split
+    ///  * terms in Fluent inputs are included
+    ///  * names in XML inputs are not split
+    ///  * no key enums
+    ///  * no table of all keys
+    ///  * no lookup of key metadata
+    ///  * constants of type `&str`
+    pub fn new() -> Config {
+        Config {
+            inputs: vec![],
+            input_globs: vec![],
+            input_format: None,
+            output_dir: None,
+            output_file_name: "keygen.rs".to_string(),
+            separator: ".".to_string(),
+            enable_warnings: false,
+            root_module: None,
+            visibility: Visibility::Public,
+            sort_keys: false,
+            identifier_policy: IdentifierPolicy::Sanitize,
+            constant_case: Case::AsIs,
+            module_case: Case::AsIs,
+            toml_array_tables: ArrayOfTables::Skip,
+            split_env_names: false,
+            fluent_terms: true,
+            xml_name_separator: None,
+            key_enums: false,
+            key_table: KeyTable::None,
+            key_lookup: false,
+            typed_keys: false,
+        }
+    }
+
+    /// Path to an input file in any format as specified in `README.md`.
+    ///
+    /// May be called multiple times, the keys of all input files are merged into one tree.
+    pub fn input(mut self, input: impl Into<PathBuf>) -> Config {
+        self.inputs.push(input.into());
+        self
+    }
+
+    /// Adds all given paths as input files, see [`input`](Config::input).
+    pub fn inputs<P: Into<PathBuf>>(mut self, inputs: impl IntoIterator<Item = P>) -> Config {
+        self.inputs.extend(inputs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds all files matching the glob pattern (e.g. `"keys/*.keys"` or `"keys/**/*.yaml"`) as input files.
+    ///
+    /// `*` and `?` match any characters within a path component, `**` matches any number of directories.
+    /// Components are separated by `/` and the matched files are merged in the order of their paths.
+    pub fn input_glob(mut self, pattern: &str) -> Config {
+        self.input_globs.push(pattern.to_string());
+        self
+    }
+
+    /// Format of the input. If this is not set, the format is detected from the extension of each input file
+    /// (see [`InputFormat::from_path`]).
+    pub fn input_format(mut self, input_format: InputFormat) -> Config {
+        self.input_format = Some(input_format);
+        self
+    }
+
+    /// Directory where the output file is generated. The necessary directories will be created.
+    ///
+    /// If this is not set, cargo's `OUT_DIR` is used in build-scripts and `generated/keygen` otherwise.
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Config {
+        self.output_dir = Some(output_dir.into());
+        self
+    }
+
+    /// Name of the generated file inside the output directory.
+    pub fn output_file_name(mut self, output_file_name: &str) -> Config {
+        self.output_file_name = output_file_name.to_string();
+        self
+    }
+
+    /// Separator to use in the generated constants (e.g. `"."`, `":"`, `"/"`).
+    ///
+    /// Key files may override the separator for the descendants of a key with the attribute `[separator="..."]`.
+    pub fn separator(mut self, separator: &str) -> Config {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Whether the generated code should trigger warnings, like naming-conventions or unused code.
+    /// If set to `false`, those warnings will be ignored.
+    pub fn enable_warnings(mut self, enable_warnings: bool) -> Config {
+        self.enable_warnings = enable_warnings;
+        self
+    }
+
+    /// Wraps all generated code in a module with the given name.
+    /// The name is not part of the generated key strings.
+    /// Generating fails if the name is not a valid rust identifier, keywords are emitted as raw identifiers.
+    pub fn root_module(mut self, root_module: &str) -> Config {
+        self.root_module = Some(root_module.to_string());
+        self
+    }
+
+    /// Visibility of the generated modules and constants.
+    pub fn visibility(mut self, visibility: Visibility) -> Config {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Whether keys are sorted alphabetically in the generated code instead of keeping the order of the input.
+    pub fn sort_keys(mut self, sort_keys: bool) -> Config {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// How key segments that are not valid rust identifiers (e.g. `http-port` or `2fa`) are handled.
+    ///
+    /// Keywords are always emitted as raw identifiers (e.g. `r#type`). The segments `self`, `Self`, `super` and `crate`
+    /// can not be represented and always result in an error.
+    pub fn identifier_policy(mut self, identifier_policy: IdentifierPolicy) -> Config {
+        self.identifier_policy = identifier_policy;
+        self
+    }
+
+    /// Naming convention of the generated constants. The key strings are not affected.
+    ///
+    /// If set to [`Case::ScreamingSnakeCase`], the `non_upper_case_globals` lint is no longer suppressed.
+    pub fn constant_case(mut self, constant_case: Case) -> Config {
+        self.constant_case = constant_case;
+        self
+    }
+
+    /// Naming convention of the generated modules. The key strings are not affected.
+    ///
+    /// If set to [`Case::SnakeCase`], the `non_snake_case` lint is no longer suppressed.
+    pub fn module_case(mut self, module_case: Case) -> Config {
+        self.module_case = module_case;
+        self
+    }
+
+    /// How arrays of tables (`[[name]]`) in TOML inputs are handled.
+    pub fn toml_array_tables(mut self, toml_array_tables: ArrayOfTables) -> Config {
+        self.toml_array_tables = toml_array_tables;
+        self
+    }
+
+    /// Whether variable names in dotenv inputs are split at `_` into hierarchy levels.
+    ///
+    /// Combined with `.separator("_")` the generated key strings are the original variable names,
+    /// e.g. `DATABASE_POOL_SIZE` is generated as `DATABASE::POOL::SIZE` with the value `"DATABASE_POOL_SIZE"`.
+    pub fn split_env_names(mut self, split_env_names: bool) -> Config {
+        self.split_env_names = split_env_names;
+        self
+    }
+
+    /// Whether terms (`-name`) in Fluent inputs are included. Terms can only be referenced from within the resource,
+    /// so it may be desirable to exclude them.
+    pub fn fluent_terms(mut self, fluent_terms: bool) -> Config {
+        self.fluent_terms = fluent_terms;
+        self
+    }
+
+    /// Character at which resource names in XML inputs are split into hierarchy levels (e.g. `'_'` or `'.'`).
+    ///
+    /// Set the same character as [`separator`](Config::separator) to keep the original names as key strings.
+    pub fn xml_name_separator(mut self, xml_name_separator: char) -> Config {
+        self.xml_name_separator = Some(xml_name_separator);
+        self
+    }
+
+    /// Whether every generated module also contains an enum `Key` of its keys, so strings can be parsed into typed keys.
+    ///
+    /// Leaves become unit variants and modules become variants wrapping the enum of the module, e.g. `server.tls.cert`
+    /// is `server::Key::Tls(server::tls::Key::Cert)`. The enums implement `as_str`, `Display`, `FromStr` and `TryFrom<&str>`,
+    /// and list all their keys in `Key::ALL`. Keys containing placeholders are not part of the enums.
+    ///
+    /// The top level keys only get an enum if a [`root_module`](Config::root_module) is configured.
+    pub fn key_enums(mut self, key_enums: bool) -> Config {
+        self.key_enums = key_enums;
+        self
+    }
+
+    /// Whether the constant `ALL_KEYS: &[&str]` and the function `is_known_key(&str) -> bool` are generated at the top level,
+    /// e.g. to validate user-supplied configurations.
+    ///
+    /// `ALL_KEYS` is sorted, so `is_known_key` is a binary search. Keys containing placeholders are not part of the table.
+    pub fn key_table(mut self, key_table: KeyTable) -> Config {
+        self.key_table = key_table;
+        self
+    }
+
+    /// Whether the function `lookup(&str) -> Option<&'static KeyInfo>` is generated at the top level, returning the metadata
+    /// of a key: its path segments, doc comment, declared type (attribute `[type="..."]` in key files) and source location.
+    ///
+    /// The lookup is backed by a minimal perfect hash table, which is computed at generation time, so the generated code
+    /// needs no dependencies. Keys of leaves and the `_BASE` keys of modules are included, keys containing placeholders are not.
+    pub fn key_lookup(mut self, key_lookup: bool) -> Config {
+        self.key_lookup = key_lookup;
+        self
+    }
+
+    /// Whether the constants are of the newtype `Key` instead of `&str`, so APIs can demand a key from the tree.
+    ///
+    /// The newtype is generated at the top level and can only be created by the crate including the generated code.
+    /// It implements `Deref<Target = str>`, `AsRef<str>`, `Borrow<str>` and `Display`, and hashes and compares like `str`,
+    /// so it can be used to look up maps with `str` keys. Keys containing placeholders are still generated as `String`.
+    ///
+    /// Can not be combined with [`key_enums`](Config::key_enums) and a [`root_module`](Config::root_module), as both
+    /// generate a type `Key` in the root module.
+    pub fn typed_keys(mut self, typed_keys: bool) -> Config {
+        self.typed_keys = typed_keys;
+        self
+    }
+
+    /// Reads the configured input files, generates the code and writes it to the output file.
+    ///
+    /// The keys of all input files are merged into one tree. Keys defined in several files are merged as well,
+    /// as long as their definitions do not contradict each other (e.g. with different doc comments).
+    ///
+    /// In build-scripts `cargo:rerun-if-changed` is printed for every input file (including files referenced with `@include`)
+    /// and for the base directory of every glob pattern.
+    ///
+    /// # Errors
+    /// Returns a [`KeygenError`] if no input is configured, if a glob pattern matches no files, if an input file can not be read
+    /// or parsed, if the input files contradict each other, or if the output can not be written.
+    pub fn generate(&self) -> Result<(), KeygenError> {
+        let out_dir = env::var_os("OUT_DIR");
+        if out_dir.is_some() {
+            for pattern in &self.input_globs {
+                println!("cargo:rerun-if-changed={}", glob::base_dir(pattern).display());
+            }
+            for input in &self.inputs {
+                println!("cargo:rerun-if-changed={}", input.display());
+            }
+        }
+
+        let (output, files) = self.generate_to_string()?;
+        if out_dir.is_some() {
+            for file in files.iter().filter(|f| self.inputs.contains(f).not()) {
+                println!("cargo:rerun-if-changed={}", file.display());
+            }
+        }
+
+        let out_path = self.output_dir.clone().unwrap_or_else(|| default_output_dir(out_dir));
+        let out_path = out_path.as_path();
+        create_dir_all(out_path).map_err(|e| KeygenError::io(out_path, e))?;
+        let out_file_path = out_path.join(&self.output_file_name);
+        File::create(&out_file_path)
+            .and_then(|mut f| f.write_all(output.as_bytes()))
+            .map_err(|e| KeygenError::io(&out_file_path, e))
+    }
+
+    /// Reads the configured input files like [`generate`](Config::generate), but returns the generated code instead of
+    /// writing it to a file.
+    ///
+    /// Besides the code, the paths of all files that were read are returned, including files referenced with `@include`.
+    /// They can be used to track changes of the input, e.g. in procedural macros.
+    ///
+    /// # Errors
+    /// Returns a [`KeygenError`] if no input is configured, if a glob pattern matches no files, if an input file can not be read
+    /// or parsed, or if the input files contradict each other.
+    pub fn generate_to_string(&self) -> Result<(String, Vec<PathBuf>), KeygenError> {
+        let mut inputs = self.inputs.clone();
+        for pattern in &self.input_globs {
+            let matches = glob::expand(pattern)?;
+            if matches.is_empty() {
+                return Err(KeygenError::validation(format!("the pattern `{}` matches no input files", pattern), None));
+            }
+            for input in matches {
+                if inputs.contains(&input).not() {
+                    inputs.push(input);
+                }
+            }
+        }
+        if inputs.is_empty() {
+            return Err(KeygenError::validation("no input file configured", None));
+        }
+
+        let mut root = KeyElement::new("");
+        let mut included = vec![];
+        for input in &inputs {
+            let mut input_str = "".to_string();
+            File::open(input)
+                .and_then(|mut f| f.read_to_string(&mut input_str))
+                .map_err(|e| KeygenError::io(input, e))?;
+
+            let input_format = self.input_format.unwrap_or_else(|| InputFormat::from_path(input));
+            let mut compiled = KeyElement::new("");
+            compiled.children = input_format.compile(&input_str, Some(input), self, &mut included)
+                .map_err(|e| e.with_path(input))?;
+            compiled.set_path(input);
+            root.merge(compiled, "")?;
+        }
+        let output = generate_from_keys(root.children, self)?;
+
+        inputs.extend(included.into_iter().filter(|f| inputs.contains(f).not()).collect::<Vec<PathBuf>>());
+        Ok((output, inputs))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+fn default_output_dir(out_dir: Option<OsString>) -> PathBuf {
+    out_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("generated/keygen"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_defaults_to_cargo_out_dir() {
+        assert_eq!(PathBuf::from("target/out"), default_output_dir(Some(OsString::from("target/out"))));
+        assert_eq!(PathBuf::from("generated/keygen"), default_output_dir(None));
+    }
+}
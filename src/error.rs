@@ -0,0 +1,208 @@
+//! Error type returned by all fallible operations of this crate.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Position of a problem inside an input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Input file the location refers to. `None` if the input was not read from a file.
+    pub path: Option<PathBuf>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number, counted in characters.
+    pub column: usize,
+    /// The complete source line the location points into.
+    pub snippet: String,
+}
+
+impl SourceLocation {
+    /// Creates a location pointing at the given (1-based) `line` and `column` of `snippet`.
+    pub fn new(line: usize, column: usize, snippet: &str) -> SourceLocation {
+        SourceLocation {
+            path: None,
+            line,
+            column,
+            snippet: snippet.to_string(),
+        }
+    }
+
+    fn fmt_diagnostic(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        match &self.path {
+            Some(path) => writeln!(f, "{}--> {}:{}:{}", gutter, path.display(), self.line, self.column)?,
+            None => writeln!(f, "{}--> <input>:{}:{}", gutter, self.line, self.column)?,
+        }
+
+        // Tabs are expanded, so the caret lines up with the printed snippet.
+        let offset: usize = self.snippet.chars()
+            .take(self.column.saturating_sub(1))
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.snippet.replace('\t', "    "))?;
+        write!(f, "{} | {}^", gutter, " ".repeat(offset))
+    }
+}
+
+/// Errors that may occur while generating code.
+#[derive(Debug)]
+pub enum KeygenError {
+    /// Reading an input file or writing the output failed.
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// The input is malformed.
+    Parse {
+        message: String,
+        location: SourceLocation,
+        /// Other locations involved in the problem (e.g. the `@include` directives leading to the file), each with an explanation.
+        notes: Vec<(String, SourceLocation)>,
+    },
+    /// The key tree could not be translated into rust code.
+    Codegen {
+        message: String,
+        location: Option<SourceLocation>,
+    },
+    /// The key tree is well-formed, but violates a rule of the generator.
+    Validation {
+        message: String,
+        location: Option<SourceLocation>,
+        /// Other locations involved in the problem (e.g. the first definition of a duplicate), each with an explanation.
+        notes: Vec<(String, SourceLocation)>,
+    },
+}
+
+impl KeygenError {
+    pub(crate) fn io(path: &Path, source: io::Error) -> KeygenError {
+        KeygenError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    pub(crate) fn parse(message: impl Into<String>, location: SourceLocation) -> KeygenError {
+        KeygenError::Parse {
+            message: message.into(),
+            location,
+            notes: vec![],
+        }
+    }
+
+    pub(crate) fn validation(message: impl Into<String>, location: Option<SourceLocation>) -> KeygenError {
+        KeygenError::Validation {
+            message: message.into(),
+            location,
+            notes: vec![],
+        }
+    }
+
+    /// Adds a note pointing to another location involved in the problem.
+    /// Errors without source locations are returned unchanged.
+    pub(crate) fn with_note(mut self, note: impl Into<String>, location: SourceLocation) -> KeygenError {
+        if let KeygenError::Parse { notes, .. } | KeygenError::Validation { notes, .. } = &mut self {
+            notes.push((note.into(), location));
+        }
+        self
+    }
+
+    /// Returns the source location this error refers to, if there is one.
+    pub fn location(&self) -> Option<&SourceLocation> {
+        match self {
+            KeygenError::Io { .. } => None,
+            KeygenError::Parse { location, .. } => Some(location),
+            KeygenError::Codegen { location, .. } |
+            KeygenError::Validation { location, .. } => location.as_ref(),
+        }
+    }
+
+    /// Attaches the path of the input file to the source location of this error, unless it already has one.
+    pub fn with_path(mut self, path: &Path) -> KeygenError {
+        let locations = match &mut self {
+            KeygenError::Io { .. } => vec![],
+            KeygenError::Parse { location, notes, .. } => {
+                std::iter::once(location)
+                    .chain(notes.iter_mut().map(|(_, l)| l))
+                    .collect()
+            }
+            KeygenError::Codegen { location, .. } => location.iter_mut().collect(),
+            KeygenError::Validation { location, notes, .. } => {
+                location.iter_mut()
+                    .chain(notes.iter_mut().map(|(_, l)| l))
+                    .collect()
+            }
+        };
+        for location in locations {
+            if location.path.is_none() {
+                location.path = Some(path.to_path_buf());
+            }
+        }
+        self
+    }
+}
+
+impl Display for KeygenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeygenError::Io { path, source } => write!(f, "error: {}: {}", path.display(), source),
+            KeygenError::Parse { message, location, notes } => {
+                writeln!(f, "error: {}", message)?;
+                location.fmt_diagnostic(f)?;
+                fmt_notes(f, notes)
+            }
+            KeygenError::Codegen { message, location } => {
+                write!(f, "error: {}", message)?;
+                if let Some(location) = location {
+                    writeln!(f)?;
+                    location.fmt_diagnostic(f)?;
+                }
+                Ok(())
+            }
+            KeygenError::Validation { message, location, notes } => {
+                write!(f, "error: {}", message)?;
+                if let Some(location) = location {
+                    writeln!(f)?;
+                    location.fmt_diagnostic(f)?;
+                }
+                fmt_notes(f, notes)
+            }
+        }
+    }
+}
+
+fn fmt_notes(f: &mut Formatter<'_>, notes: &[(String, SourceLocation)]) -> std::fmt::Result {
+    for (note, location) in notes {
+        write!(f, "\nnote: {}\n", note)?;
+        location.fmt_diagnostic(f)?;
+    }
+    Ok(())
+}
+
+impl Error for KeygenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KeygenError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_error_renders_caret_diagnostic() {
+        let mut location = SourceLocation::new(12, 3, "\tkey");
+        location.path = Some(PathBuf::from("keys/input.keys"));
+        let error = KeygenError::parse("illegal indentation", location);
+
+        assert_eq!(
+            "error: illegal indentation\n  --> keys/input.keys:12:3\n   |\n12 |     key\n   |      ^",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Reader for dotenv (`.env`) files.
+
+use std::ops::Not;
+
+use crate::{KeyElement, KeygenError, SourceLocation};
+
+/// Reads the variable names of the dotenv file, values are ignored.
+///
+/// If `split_names` is set, the names are split at `_` into hierarchy levels.
+pub(crate) fn compile_env(input: &str, split_names: bool) -> Result<Vec<KeyElement>, KeygenError> {
+    let mut root = KeyElement::new("");
+    for (name, _, origin) in parse_env(input)? {
+        if split_names {
+            let path = name.split('_')
+                .filter(|s| s.is_empty().not())
+                .collect::<Vec<&str>>()
+                .join(".");
+            root.create_key(&path, &origin);
+        } else {
+            root.create_child(&name, &origin);
+        }
+    }
+    Ok(root.children)
+}
+
+/// Parses the dotenv file into its variable names and unquoted values, together with the location of each name.
+pub(crate) fn parse_env(input: &str) -> Result<Vec<(String, String, SourceLocation)>, KeygenError> {
+    let mut entries = vec![];
+    let mut lines = input.lines().enumerate();
+    while let Some((line_index, ln)) = lines.next() {
+        let content = ln.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let content = content.strip_prefix("export ").map_or(content, str::trim_start);
+        let column = ln.chars().count() - content.chars().count() + 1;
+        let origin = SourceLocation::new(line_index + 1, column, ln);
+
+        let (name, value) = content.split_once('=')
+            .ok_or_else(|| KeygenError::parse("expected `NAME=value`", origin.clone()))?;
+        let name = name.trim_end();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(KeygenError::parse(format!("invalid variable name `{}`", name), origin));
+        }
+
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'' | '`')) => {
+                // Quoted values may span multiple lines.
+                let mut quoted = value[1..].to_string();
+                loop {
+                    if let Some(value) = unquote(&quoted, quote) {
+                        break value;
+                    }
+                    match lines.next() {
+                        Some((_, next)) => {
+                            quoted.push('\n');
+                            quoted.push_str(next);
+                        }
+                        None => return Err(KeygenError::parse("unterminated quoted value", origin)),
+                    }
+                }
+            }
+            _ => value.split(" #").next().unwrap_or_default().trim_end().to_string(),
+        };
+        entries.push((name.to_string(), value, origin));
+    }
+    Ok(entries)
+}
+
+/// Returns the content up to the closing `quote`, or `None` if the quote is not closed.
+/// Escapes are only processed in double quoted values.
+fn unquote(quoted: &str, quote: char) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => return Some(value),
+            '\\' if quote == '"' => match chars.next()? {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                other => value.push(other),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_names_are_split_into_levels() {
+        let input = "DATABASE_POOL_SIZE=10\nexport DATABASE_HOST=\"db\\nhost\"\n# comment\nDATABASE_PASSWORD='multi\nline'\n";
+        let keys = compile_env(input, true).unwrap();
+        let database = &keys[0];
+        assert_eq!("DATABASE", database.name);
+        let names: Vec<&str> = database.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["POOL", "HOST", "PASSWORD"], names);
+        assert_eq!(Some(SourceLocation::new(2, 17, "export DATABASE_HOST=\"db\\nhost\"")), database.children[1].origin);
+    }
+
+    #[test]
+    fn env_values_are_unquoted() {
+        let input = "A=plain # comment\nB=\"x\\\"y\"\nC='multi\nline'\n";
+        let values: Vec<String> = parse_env(input).unwrap().into_iter().map(|(_, v, _)| v).collect();
+        assert_eq!(vec!["plain", "x\"y", "multi\nline"], values);
+        assert!(matches!(parse_env("A=\"open"), Err(KeygenError::Parse { .. })));
+    }
+}
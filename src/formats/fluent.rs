@@ -0,0 +1,152 @@
+//! Reader for Project Fluent (`.ftl`) files.
+
+use std::ops::Not;
+
+use crate::{KeyElement, KeygenError, SourceLocation};
+
+/// Reads the message ids of the Fluent resource.
+///
+/// Attributes become child keys of their message. Terms (`-name`) are only included if `include_terms` is set.
+/// A comment directly preceding a message becomes its doc comment.
+pub(crate) fn compile_fluent(input: &str, include_terms: bool) -> Result<Vec<KeyElement>, KeygenError> {
+    let mut root = KeyElement::new("");
+    let mut comment: Vec<&str> = vec![];
+    // Index of the entry that attributes are added to, `None` for skipped terms.
+    let mut current_entry: Option<usize> = None;
+    // Placeables may span multiple lines, their content (e.g. the variants of a select expression) may start in column 0.
+    let mut open_placeables = 0;
+
+    for (line_index, ln) in input.lines().enumerate() {
+        if ln.trim().is_empty() {
+            comment.clear();
+            continue;
+        }
+
+        if open_placeables > 0 || ln.starts_with(char::is_whitespace) {
+            let in_placeable = open_placeables > 0;
+            open_placeables = count_open_placeables(ln, open_placeables);
+            let content = ln.trim_start();
+            let Some(attribute) = content.strip_prefix('.').filter(|_| in_placeable.not()) else {
+                // Continuation of a multiline value.
+                continue;
+            };
+            let Some(entry) = current_entry else {
+                continue;
+            };
+            let column = ln.chars().count() - content.chars().count() + 2;
+            let origin = SourceLocation::new(line_index + 1, column, ln);
+            let name = parse_identifier(attribute, &origin)?;
+            root.children[entry].create_child(name, &origin);
+            continue;
+        }
+
+        if let Some(text) = ln.strip_prefix('#') {
+            // Only single `#` comments belong to a message, `##` and `###` are group and resource comments.
+            if text.starts_with('#').not() {
+                comment.push(text.strip_prefix(' ').unwrap_or(text));
+            }
+            continue;
+        }
+
+        let origin = SourceLocation::new(line_index + 1, 1, ln);
+        let (is_term, id) = match ln.strip_prefix('-') {
+            Some(term) => (true, term),
+            None => (false, ln),
+        };
+        let name = parse_identifier(id, &origin)?;
+        if id[name.len()..].trim_start().starts_with('=').not() {
+            return Err(KeygenError::parse("expected `=` after the message identifier", origin));
+        }
+        open_placeables = count_open_placeables(ln, 0);
+
+        current_entry = None;
+        if is_term.not() || include_terms {
+            let name = if is_term { &ln[..name.len() + 1] } else { name };
+            let element = root.create_child(name, &origin);
+            if comment.is_empty().not() {
+                element.doc = Some(comment.join("\n"));
+            }
+            current_entry = root.children.iter().position(|c| c.name == name);
+        }
+        comment.clear();
+    }
+    Ok(root.children)
+}
+
+/// Returns the number of placeables that are open after the line, given the number of placeables open before it.
+fn count_open_placeables(line: &str, mut open: usize) -> usize {
+    let mut previous = ' ';
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => open += 1,
+            '}' => open = open.saturating_sub(1),
+            // String literals (e.g. `{"{"}`) may contain braces. Quotes in text are not the start of a literal.
+            '"' if open > 0 && matches!(previous, '{' | '(' | ',' | ':') => {
+                while let Some(s) = chars.next() {
+                    match s {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        if c.is_whitespace().not() {
+            previous = c;
+        }
+    }
+    open
+}
+
+/// Returns the Fluent identifier (`[a-zA-Z][a-zA-Z0-9_-]*`) at the start of the text.
+fn parse_identifier<'a>(text: &'a str, origin: &SourceLocation) -> Result<&'a str, KeygenError> {
+    let length = text.find(|c: char| (c.is_ascii_alphanumeric() || c == '_' || c == '-').not()).unwrap_or(text.len());
+    let identifier = &text[..length];
+    if identifier.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        Ok(identifier)
+    } else {
+        Err(KeygenError::parse("expected a message identifier", origin.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(keys: &[KeyElement]) -> Vec<&str> {
+        keys.iter().map(|k| k.name.as_str()).collect()
+    }
+
+    #[test]
+    fn fluent_messages_and_attributes_compile() {
+        let input = include_str!("../test/messages.ftl");
+        let keys = compile_fluent(input, false).unwrap();
+        assert_eq!(vec!["welcome", "login-input", "emails"], names(&keys));
+        assert_eq!(vec!["placeholder", "aria-label"], names(&keys[1].children));
+        assert_eq!(Some("Greeting on the start page.".to_string()), keys[0].doc);
+        assert_eq!(None, keys[1].doc);
+
+        let keys = compile_fluent(input, true).unwrap();
+        assert_eq!(vec!["-brand-name", "welcome", "login-input", "emails"], names(&keys));
+    }
+
+    #[test]
+    fn fluent_placeables_may_span_lines() {
+        let input = "emails =\n    { $n ->\n[one] one {\"{\"} mail\n   *[other] {\"}\"} many\n}\n    .title = { \"{\" }\nnext = x\n";
+        let keys = compile_fluent(input, false).unwrap();
+        assert_eq!(vec!["emails", "next"], names(&keys));
+        assert_eq!(vec!["title"], names(&keys[0].children));
+    }
+
+    #[test]
+    fn invalid_fluent_entries_are_reported() {
+        match compile_fluent("valid = ok\n*invalid = no\n", false) {
+            Err(KeygenError::Parse { location, .. }) => assert_eq!(2, location.line),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
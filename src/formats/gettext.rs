@@ -0,0 +1,150 @@
+//! Reader for gettext (`.po`, `.pot`) catalogs.
+
+use std::ops::Not;
+
+use crate::{KeyElement, KeygenError, SourceLocation};
+
+/// The entry that is currently read.
+#[derive(Default)]
+struct PoEntry<'a> {
+    context: Option<(String, SourceLocation)>,
+    id: Option<(String, SourceLocation)>,
+    doc: Vec<&'a str>,
+    obsolete: bool,
+}
+
+/// Field of the entry that continuation lines (`"..."`) are appended to.
+enum Field {
+    Context,
+    Id,
+    Other,
+}
+
+/// Reads the message ids of the catalog.
+///
+/// Messages with a context (`msgctxt`) are created below a key named after the context.
+/// Extracted comments (`#.`) become doc comments. The header and obsolete entries are ignored.
+pub(crate) fn compile_po(input: &str) -> Result<Vec<KeyElement>, KeygenError> {
+    let mut root = KeyElement::new("");
+    let mut entry = PoEntry::default();
+    let mut field = Field::Other;
+
+    for (line_index, ln) in input.lines().enumerate() {
+        let content = ln.trim();
+        let column = ln.chars().count() - ln.trim_start().chars().count() + 1;
+        let origin = SourceLocation::new(line_index + 1, column, ln);
+
+        // Comments precede the keywords of their entry, so a comment after a message id starts the next entry.
+        // Obsolete entries consist of `#~` lines only and end at the first other line.
+        let obsolete_line = content.starts_with("#~");
+        if (entry.obsolete && obsolete_line.not()) || (entry.obsolete.not() && entry.id.is_some() && content.starts_with('#')) {
+            add_entry(&mut root, std::mem::take(&mut entry));
+        }
+
+        if content.is_empty() {
+            add_entry(&mut root, std::mem::take(&mut entry));
+        } else if obsolete_line {
+            entry.obsolete = true;
+        } else if let Some(doc) = content.strip_prefix("#.") {
+            entry.doc.push(doc.trim());
+        } else if content.starts_with('#') {
+            continue;
+        } else if let Some(value) = content.strip_prefix("msgctxt") {
+            if entry.id.is_some() {
+                add_entry(&mut root, std::mem::take(&mut entry));
+            }
+            entry.context = Some((parse_string(value, &origin)?, origin));
+            field = Field::Context;
+        } else if let Some(value) = content.strip_prefix("msgid").filter(|v| v.starts_with('_').not()) {
+            if entry.id.is_some() {
+                add_entry(&mut root, std::mem::take(&mut entry));
+            }
+            entry.id = Some((parse_string(value, &origin)?, origin));
+            field = Field::Id;
+        } else if content.starts_with("msgid_plural") || content.starts_with("msgstr") {
+            field = Field::Other;
+        } else if content.starts_with('"') {
+            let value = parse_string(content, &origin)?;
+            match field {
+                Field::Context => entry.context.iter_mut().for_each(|(c, _)| c.push_str(&value)),
+                Field::Id => entry.id.iter_mut().for_each(|(i, _)| i.push_str(&value)),
+                Field::Other => {}
+            }
+        } else {
+            return Err(KeygenError::parse("expected a keyword, a string or a comment", origin));
+        }
+    }
+    add_entry(&mut root, entry);
+    Ok(root.children)
+}
+
+fn add_entry(root: &mut KeyElement, entry: PoEntry) {
+    let Some((id, origin)) = entry.id else {
+        return;
+    };
+    // The entry with the empty id is the header of the catalog.
+    if id.is_empty() || entry.obsolete {
+        return;
+    }
+
+    let parent = match &entry.context {
+        Some((context, context_origin)) => root.create_child(context, context_origin),
+        None => root,
+    };
+    let element = parent.create_child(&id, &origin);
+    if entry.doc.is_empty().not() {
+        element.doc = Some(entry.doc.join("\n"));
+    }
+}
+
+/// Parses a quoted string with C escapes, e.g. `"Hello\n"`.
+fn parse_string(text: &str, origin: &SourceLocation) -> Result<String, KeygenError> {
+    let quoted = text.trim();
+    let inner = quoted.strip_prefix('"')
+        .and_then(|q| q.strip_suffix('"'))
+        .filter(|_| quoted.len() >= 2)
+        .ok_or_else(|| KeygenError::parse("expected a quoted string", origin.clone()))?;
+
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some(other) => value.push(other),
+            None => return Err(KeygenError::parse("unterminated escape sequence", origin.clone())),
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn po_messages_compile() {
+        let input = include_str!("../test/messages.po");
+        let keys = compile_po(input).unwrap();
+        let names: Vec<&str> = keys.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(vec!["Welcome to the application!", "menu", "You have one unread email."], names);
+        assert_eq!(Some("Shown on the start page.".to_string()), keys[0].doc);
+        assert_eq!("Open file", keys[1].children[0].name);
+        assert_eq!(Some(SourceLocation::new(11, 1, "msgid \"Open file\"")), keys[1].children[0].origin);
+    }
+
+    #[test]
+    fn po_entries_end_without_blank_lines() {
+        let input = "msgid \"a\"\nmsgstr \"A\"\n#~ msgid \"old\"\n#~ msgstr \"Alt\"\nmsgid \"b\"\nmsgstr \"B\"\n#. Doc of c\nmsgid \"c\"\n";
+        let keys = compile_po(input).unwrap();
+        let names: Vec<&str> = keys.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(vec!["a", "b", "c"], names);
+        assert_eq!(None, keys[1].doc);
+        assert_eq!(Some("Doc of c".to_string()), keys[2].doc);
+    }
+}
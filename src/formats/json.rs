@@ -0,0 +1,264 @@
+//! Reader for JSON documents.
+//!
+//! The parser is kept minimal on purpose: it only records what is needed to build the key tree,
+//! but keeps the position of every object key for diagnostics.
+
+use std::ops::Not;
+
+use crate::formats::location_at;
+use crate::{KeyElement, KeygenError};
+
+/// A parsed JSON value.
+#[derive(Debug, PartialEq)]
+pub(crate) enum JsonValue {
+    Object(Vec<JsonMember>),
+    Array(Vec<JsonValue>),
+    String(String),
+    /// Numbers, booleans and `null`.
+    Other,
+}
+
+/// A member of a JSON object.
+#[derive(Debug, PartialEq)]
+pub(crate) struct JsonMember {
+    pub(crate) name: String,
+    /// Byte offset of the name in the input.
+    pub(crate) offset: usize,
+    pub(crate) value: JsonValue,
+}
+
+impl JsonValue {
+    /// Returns the member with the given name, if this is an object containing it.
+    pub(crate) fn member(&self, name: &str) -> Option<&JsonMember> {
+        match self {
+            JsonValue::Object(members) => members.iter().find(|m| m.name == name),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the member with the given name, if this is an object containing it.
+    pub(crate) fn get(&self, name: &str) -> Option<&JsonValue> {
+        self.member(name).map(|m| &m.value)
+    }
+}
+
+struct JsonParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, message: &str) -> KeygenError {
+        KeygenError::parse(message, location_at(self.input, self.pos))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), KeygenError> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", expected as char)))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, KeygenError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => self.parse_string().map(JsonValue::String),
+            Some(_) => self.parse_literal(),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, KeygenError> {
+        self.expect(b'{')?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected object key"));
+            }
+            let offset = self.pos;
+            let name = self.parse_string()?;
+            self.expect(b':')?;
+            let value = self.parse_value()?;
+            members.push(JsonMember { name, offset, value });
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, KeygenError> {
+        self.expect(b'[')?;
+        let mut elements = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(elements));
+        }
+        loop {
+            elements.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(elements));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, KeygenError> {
+        self.expect(b'"')?;
+        let mut value = String::new();
+        let mut chars = self.input[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, e)| e) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.parse_hex(&mut chars, i)?;
+                            if (0xD800..0xDC00).contains(&code) {
+                                // High surrogate, the low surrogate has to follow as another escape.
+                                let low = match (chars.next(), chars.next()) {
+                                    (Some((_, '\\')), Some((_, 'u'))) => self.parse_hex(&mut chars, i)?,
+                                    _ => 0,
+                                };
+                                if (0xDC00..0xE000).contains(&low).not() {
+                                    return Err(self.error_at(i, "invalid unicode escape"));
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error_at(i, "invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error_at(i, "invalid escape sequence")),
+                    };
+                    value.push(escaped);
+                }
+                c if (c as u32) < 0x20 => return Err(self.error_at(i, "control character in string")),
+                c => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn parse_hex(&self, chars: &mut std::str::CharIndices, escape_start: usize) -> Result<u32, KeygenError> {
+        let digits: String = chars.take(4).map(|(_, c)| c).collect();
+        if digits.len() != 4 {
+            return Err(self.error_at(escape_start, "invalid unicode escape"));
+        }
+        u32::from_str_radix(&digits, 16).map_err(|_| self.error_at(escape_start, "invalid unicode escape"))
+    }
+
+    fn error_at(&self, relative: usize, message: &str) -> KeygenError {
+        KeygenError::parse(message, location_at(self.input, self.pos + relative))
+    }
+
+    fn parse_literal(&mut self) -> Result<JsonValue, KeygenError> {
+        let rest = &self.input[self.pos..];
+        let length = rest.find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c))).unwrap_or(rest.len());
+        let literal = &rest[..length];
+        if matches!(literal, "true" | "false" | "null") || (literal.is_empty().not() && literal.parse::<f64>().is_ok()) {
+            self.pos += length;
+            Ok(JsonValue::Other)
+        } else {
+            Err(self.error("expected a JSON value"))
+        }
+    }
+}
+
+/// Parses the input as a single JSON value.
+pub(crate) fn parse_json(input: &str) -> Result<JsonValue, KeygenError> {
+    let mut parser = JsonParser { input, pos: 0 };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos < input.len() {
+        return Err(parser.error("unexpected content after the JSON value"));
+    }
+    Ok(value)
+}
+
+/// Reads the keys of all (nested) objects in the JSON document.
+///
+/// The names of object members become keys, nested objects become child keys. Arrays and all other values are ignored.
+pub(crate) fn compile_json(input: &str) -> Result<Vec<KeyElement>, KeygenError> {
+    let mut root = KeyElement::new("");
+    compile_object(&parse_json(input)?, &mut root, input);
+    Ok(root.children)
+}
+
+fn compile_object(value: &JsonValue, parent: &mut KeyElement, input: &str) {
+    if let JsonValue::Object(members) = value {
+        for member in members {
+            let child = parent.create_child(&member.name, &location_at(input, member.offset));
+            compile_object(&member.value, child, input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::expecded_structure;
+    use crate::SourceLocation;
+
+    #[test]
+    fn json_input_compiles() {
+        let input = include_str!("../test/hierarchical.json");
+        assert_eq!(expecded_structure(), compile_json(input).unwrap());
+    }
+
+    #[test]
+    fn json_strings_are_unescaped() {
+        let keys = compile_json(r#"{"a\"bä😀": null}"#).unwrap();
+        assert_eq!("a\"bä😀", keys[0].name);
+    }
+
+    #[test]
+    fn json_syntax_errors_are_reported() {
+        match compile_json("{\n  \"a\": {\n    \"b\" 1\n  }\n}") {
+            Err(KeygenError::Parse { location, .. }) => assert_eq!(SourceLocation::new(3, 9, "    \"b\" 1"), location),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Reader for JSON Schemas.
+
+use crate::formats::json::{parse_json, JsonValue};
+use crate::formats::location_at;
+use crate::{KeyElement, KeygenError};
+
+/// Keywords whose subschemas describe the same instance as the schema containing them.
+const COMBINATORS: &[&str] = &["allOf", "anyOf", "oneOf"];
+
+struct SchemaWalker<'a> {
+    input: &'a str,
+    root: &'a JsonValue,
+    /// References that are currently being resolved, to stop at recursive schemas.
+    active_refs: Vec<&'a str>,
+}
+
+impl<'a> SchemaWalker<'a> {
+    fn compile_schema(&mut self, schema: &'a JsonValue, parent: &mut KeyElement) -> Result<(), KeygenError> {
+        if let Some(member) = schema.member("$ref") {
+            if let JsonValue::String(reference) = &member.value {
+                if self.active_refs.contains(&reference.as_str()) {
+                    // A recursive schema describes infinitely many paths, so the recursion stops here.
+                    return Ok(());
+                }
+                let target = self.resolve(reference).ok_or_else(|| {
+                    KeygenError::validation(
+                        format!("the reference `{}` can not be resolved", reference),
+                        Some(location_at(self.input, member.offset)),
+                    )
+                })?;
+                self.active_refs.push(reference);
+                self.compile_schema(target, parent)?;
+                self.active_refs.pop();
+            }
+        }
+
+        if let Some(JsonValue::Object(properties)) = schema.get("properties") {
+            for property in properties {
+                let child = parent.create_child(&property.name, &location_at(self.input, property.offset));
+                self.compile_schema(&property.value, child)?;
+            }
+        }
+
+        for combinator in COMBINATORS {
+            if let Some(JsonValue::Array(subschemas)) = schema.get(combinator) {
+                for subschema in subschemas {
+                    self.compile_schema(subschema, parent)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves a reference within the document (e.g. `#/$defs/address`).
+    fn resolve(&self, reference: &str) -> Option<&'a JsonValue> {
+        let pointer = reference.strip_prefix('#')?;
+        let mut target = self.root;
+        for token in pointer.split('/').skip(1) {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            target = match target {
+                JsonValue::Object(_) => target.get(&token)?,
+                JsonValue::Array(elements) => elements.get(token.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+}
+
+/// Reads the property paths described by the JSON Schema.
+///
+/// Every property in `properties` becomes a key, the properties of its schema become child keys.
+/// Local references (e.g. to `$defs` or `definitions`) and the subschemas of `allOf`, `anyOf` and `oneOf` are followed.
+pub(crate) fn compile_json_schema(input: &str) -> Result<Vec<KeyElement>, KeygenError> {
+    let schema = parse_json(input)?;
+    let mut root = KeyElement::new("");
+    let mut walker = SchemaWalker {
+        input,
+        root: &schema,
+        active_refs: vec![],
+    };
+    walker.compile_schema(&schema, &mut root)?;
+    Ok(root.children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::expecded_structure;
+
+    #[test]
+    fn json_schema_input_compiles() {
+        let input = include_str!("../test/hierarchical.schema.json");
+        assert_eq!(expecded_structure(), compile_json_schema(input).unwrap());
+    }
+
+    #[test]
+    fn unresolvable_references_are_reported() {
+        let input = "{\"properties\": {\"a\": {\"$ref\": \"#/$defs/missing\"}}}";
+        match compile_json_schema(input) {
+            Err(KeygenError::Validation { location: Some(location), .. }) => assert_eq!(23, location.column),
+            other => panic!("expected validation error, got {:?}", other),
+        }
+    }
+}
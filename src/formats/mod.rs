@@ -0,0 +1,151 @@
+//! Readers for the supported input formats.
+//!
+//! Every reader translates its input into a tree of [`KeyElement`]s, which is then processed like the key format.
+
+use std::path::{Path, PathBuf};
+
+use crate::{compile_keys, Config, KeyElement, KeygenError, SourceLocation};
+
+mod env;
+mod fluent;
+mod gettext;
+mod json;
+mod json_schema;
+mod properties;
+#[cfg(feature = "toml")]
+mod toml;
+mod xml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+/// Format of an input file.
+///
+/// In the tree formats (YAML, TOML, JSON and JSON Schema) the hierarchy is defined by the nesting of the document only.
+/// Names containing `.` (e.g. `"a.b"` in JSON) are used as a single key segment and are not split.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InputFormat {
+    /// The hierarchical or enumerated key format described in `README.md`.
+    Keys,
+    /// YAML documents. The keys of mappings become keys, values are ignored.
+    /// Aliases of mappings and merge keys (`<<`) are resolved.
+    /// Requires the feature `yaml`.
+    Yaml,
+    /// TOML documents. Tables and keys (including dotted keys) become keys, values are ignored.
+    /// Arrays of tables are handled as configured with [`Config::toml_array_tables`].
+    /// Requires the feature `toml`.
+    Toml,
+    /// JSON documents. The names of (nested) object members become keys, values and arrays are ignored.
+    Json,
+    /// JSON Schemas. The property paths described by `properties` become keys.
+    /// References to definitions in the same document (e.g. in `$defs`) and `allOf`, `anyOf` and `oneOf` are followed.
+    JsonSchema,
+    /// Java `.properties` files. The keys are split at `.` into hierarchy levels, values are ignored.
+    Properties,
+    /// dotenv files. The variable names become keys, values are ignored.
+    /// Names may be split at `_` into hierarchy levels with [`Config::split_env_names`].
+    Env,
+    /// Project Fluent resources. Message ids become keys, their attributes become child keys.
+    /// Terms are only included if enabled with [`Config::fluent_terms`].
+    Fluent,
+    /// gettext catalogs. Message ids become keys, below a key named after the context (`msgctxt`) if there is one.
+    Gettext,
+    /// Android string resources. The `name` attributes of `<string>`, `<plurals>` and `<string-array>` elements become keys.
+    /// Names may be split into hierarchy levels with [`Config::xml_name_separator`].
+    Xml,
+}
+
+/// How arrays of tables (`[[name]]`) in TOML inputs are handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArrayOfTables {
+    /// Arrays of tables and their content are ignored.
+    Skip,
+    /// The array becomes a key, containing the keys of all its tables.
+    Merge,
+    /// The array becomes a key with a placeholder segment for the index, containing the keys of all its tables
+    /// (e.g. `servers.{index}.host`). Functions taking the index are generated for these keys.
+    Indexed,
+}
+
+impl InputFormat {
+    /// Detects the format from the extension of the file:
+    ///  * `.yaml`, `.yml` - [`InputFormat::Yaml`]
+    ///  * `.toml` - [`InputFormat::Toml`]
+    ///  * `.schema.json` - [`InputFormat::JsonSchema`]
+    ///  * `.json` - [`InputFormat::Json`]
+    ///  * `.properties` - [`InputFormat::Properties`]
+    ///  * `.env`, `.env.*` (e.g. `.env.local`) - [`InputFormat::Env`]
+    ///  * `.ftl` - [`InputFormat::Fluent`]
+    ///  * `.po`, `.pot` - [`InputFormat::Gettext`]
+    ///  * `.xml` - [`InputFormat::Xml`]
+    ///  * everything else - [`InputFormat::Keys`]
+    pub fn from_path(path: &Path) -> InputFormat {
+        let extension = path.extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let file_name = path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "yaml" | "yml" => InputFormat::Yaml,
+            "toml" => InputFormat::Toml,
+            "json" if path.to_string_lossy().to_ascii_lowercase().ends_with(".schema.json") => InputFormat::JsonSchema,
+            "json" => InputFormat::Json,
+            "properties" => InputFormat::Properties,
+            "env" => InputFormat::Env,
+            "ftl" => InputFormat::Fluent,
+            "po" | "pot" => InputFormat::Gettext,
+            "xml" => InputFormat::Xml,
+            _ if file_name == ".env" || file_name.starts_with(".env.") => InputFormat::Env,
+            _ => InputFormat::Keys,
+        }
+    }
+
+    /// Compiles the input into a key tree.
+    ///
+    /// `path` is the file the input was read from, files referenced by it (e.g. with `@include`) are resolved relative to it
+    /// and added to `included`.
+    pub(crate) fn compile(
+        &self,
+        input: &str,
+        path: Option<&Path>,
+        config: &Config,
+        included: &mut Vec<PathBuf>,
+    ) -> Result<Vec<KeyElement>, KeygenError> {
+        match self {
+            InputFormat::Keys => compile_keys(input, path, config, included),
+            #[cfg(feature = "yaml")]
+            InputFormat::Yaml => yaml::compile_yaml(input),
+            #[cfg(feature = "toml")]
+            InputFormat::Toml => toml::compile_toml(input, config.toml_array_tables),
+            InputFormat::Json => json::compile_json(input),
+            InputFormat::JsonSchema => json_schema::compile_json_schema(input),
+            InputFormat::Properties => properties::compile_properties(input),
+            InputFormat::Env => env::compile_env(input, config.split_env_names),
+            InputFormat::Fluent => fluent::compile_fluent(input, config.fluent_terms),
+            InputFormat::Gettext => gettext::compile_po(input),
+            InputFormat::Xml => xml::compile_xml(input, config.xml_name_separator),
+            #[allow(unreachable_patterns)]
+            _ => Err(KeygenError::validation(format!("support for {:?} input requires the feature `{}`", self, self.feature()), None)),
+        }
+    }
+
+    fn feature(&self) -> &'static str {
+        match self {
+            InputFormat::Yaml => "yaml",
+            InputFormat::Toml => "toml",
+            _ => "",
+        }
+    }
+}
+
+/// Returns the location of the given byte offset in the input.
+pub(crate) fn location_at(input: &str, offset: usize) -> SourceLocation {
+    let offset = offset.min(input.len());
+    let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = input[..line_start].matches('\n').count() + 1;
+    let column = input[line_start..offset].chars().count() + 1;
+    let snippet = input[line_start..].lines().next().unwrap_or_default();
+    SourceLocation::new(line, column, snippet)
+}
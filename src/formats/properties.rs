@@ -0,0 +1,121 @@
+//! Reader for Java `.properties` files.
+
+use crate::{KeyElement, KeygenError, SourceLocation};
+
+/// Reads the keys of the properties file. Keys are split at `.` into hierarchy levels, values are ignored.
+pub(crate) fn compile_properties(input: &str) -> Result<Vec<KeyElement>, KeygenError> {
+    let mut root = KeyElement::new("");
+    for (key, _, origin) in parse_properties(input)? {
+        root.create_key(&key, &origin);
+    }
+    Ok(root.children)
+}
+
+/// Parses the properties file into its unescaped keys and values, together with the location of each key.
+pub(crate) fn parse_properties(input: &str) -> Result<Vec<(String, String, SourceLocation)>, KeygenError> {
+    let mut entries = vec![];
+    let mut lines = input.lines().enumerate();
+    while let Some((line_index, ln)) = lines.next() {
+        let content = ln.trim_start();
+        if content.is_empty() || content.starts_with('#') || content.starts_with('!') {
+            continue;
+        }
+        let column = ln.chars().count() - content.chars().count() + 1;
+        let origin = SourceLocation::new(line_index + 1, column, ln);
+
+        // A line ending with an odd number of backslashes continues on the next line.
+        let mut logical = content.to_string();
+        while ends_with_line_continuation(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+
+        let (key, value) = split_entry(&logical);
+        entries.push((unescape(key, &origin)?, unescape(value, &origin)?, origin));
+    }
+    Ok(entries)
+}
+
+fn ends_with_line_continuation(line: &str) -> bool {
+    let backslashes = line.len() - line.trim_end_matches('\\').len();
+    backslashes % 2 == 1
+}
+
+/// Splits the logical line at the first unescaped `=`, `:` or whitespace.
+fn split_entry(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            let rest = line[i..].trim_start();
+            let rest = if c.is_whitespace() {
+                rest.strip_prefix(['=', ':']).unwrap_or(rest)
+            } else {
+                &rest[1..]
+            };
+            return (&line[..i], rest.trim_start());
+        }
+    }
+    (line, "")
+}
+
+fn unescape(escaped: &str, origin: &SourceLocation) -> Result<String, KeygenError> {
+    let mut unescaped = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('u') => {
+                let digits: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&digits, 16).ok()
+                    .filter(|_| digits.len() == 4)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| KeygenError::parse(format!("invalid unicode escape `\\u{}`", digits), origin.clone()))?;
+                unescaped.push(c);
+            }
+            Some(other) => unescaped.push(other),
+            None => {}
+        }
+    }
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::expecded_structure;
+
+    #[test]
+    fn properties_input_compiles() {
+        let input = include_str!("../test/hierarchical.properties");
+        assert_eq!(expecded_structure(), compile_properties(input).unwrap());
+    }
+
+    #[test]
+    fn properties_entries_are_unescaped() {
+        let input = "a\\=b\\:c = first \\\n    second\nkey:value\nkey2 value\n  \\u00e4 ";
+        let entries: Vec<(String, String)> = parse_properties(input).unwrap()
+            .into_iter()
+            .map(|(k, v, _)| (k, v))
+            .collect();
+        assert_eq!(vec![
+            ("a=b:c".to_string(), "first second".to_string()),
+            ("key".to_string(), "value".to_string()),
+            ("key2".to_string(), "value".to_string()),
+            ("ä".to_string(), "".to_string()),
+        ], entries);
+    }
+}
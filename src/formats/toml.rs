@@ -0,0 +1,89 @@
+//! Reader for TOML documents.
+
+use toml_edit::{Document, Item, TableLike};
+
+use crate::formats::location_at;
+use crate::{ArrayOfTables, KeyElement, KeygenError};
+
+/// Reads the tables and keys of the TOML document.
+///
+/// Tables (`[a.b]`), inline tables and dotted keys (`x.y = 1`) become nested keys, all other values are ignored.
+pub(crate) fn compile_toml(input: &str, array_tables: ArrayOfTables) -> Result<Vec<KeyElement>, KeygenError> {
+    let document = Document::parse(input).map_err(|e| {
+        let offset = e.span().map_or(0, |s| s.start);
+        KeygenError::parse(e.message().to_string(), location_at(input, offset))
+    })?;
+
+    let mut root = KeyElement::new("");
+    compile_table(document.as_table(), &mut root, input, array_tables, 0);
+    Ok(root.children)
+}
+
+/// `arrays` is the number of enclosing arrays of tables, which is used to give nested indices distinct names.
+fn compile_table(table: &dyn TableLike, parent: &mut KeyElement, input: &str, array_tables: ArrayOfTables, arrays: usize) {
+    for (name, item) in table.iter() {
+        if matches!(item, Item::ArrayOfTables(_)) && array_tables == ArrayOfTables::Skip {
+            continue;
+        }
+
+        let offset = table.key(name)
+            .and_then(|k| k.span())
+            .or_else(|| item.span())
+            .map_or(0, |s| s.start);
+        let location = location_at(input, offset);
+        let child = parent.create_child(name, &location);
+
+        if let Item::ArrayOfTables(array) = item {
+            let (tables, arrays) = if array_tables == ArrayOfTables::Indexed {
+                (child.create_child(&index_placeholder(arrays), &location), arrays + 1)
+            } else {
+                (child, arrays)
+            };
+            for element in array.iter() {
+                compile_table(element, tables, input, array_tables, arrays);
+            }
+        } else if let Some(child_table) = item.as_table_like() {
+            compile_table(child_table, child, input, array_tables, arrays);
+        }
+    }
+}
+
+/// Returns the placeholder segment for the index into an array of tables: `{index}`, `{index2}` inside of it and so on.
+fn index_placeholder(arrays: usize) -> String {
+    if arrays == 0 {
+        "{index}".to_string()
+    } else {
+        format!("{{index{}}}", arrays + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::expecded_structure;
+    use crate::SourceLocation;
+
+    #[test]
+    fn toml_input_compiles() {
+        let input = include_str!("../test/hierarchical.toml");
+        assert_eq!(expecded_structure(), compile_toml(input, ArrayOfTables::Skip).unwrap());
+    }
+
+    #[test]
+    fn toml_array_tables_are_configurable() {
+        let input = "[[servers]]\nhost = \"a\"\n[[servers]]\nport = 1\n";
+        assert!(compile_toml(input, ArrayOfTables::Skip).unwrap().is_empty());
+
+        let merged = compile_toml(input, ArrayOfTables::Merge).unwrap();
+        let names: Vec<&str> = merged[0].children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["host", "port"], names);
+        assert_eq!(Some(SourceLocation::new(4, 1, "port = 1")), merged[0].children[1].origin);
+
+        let indexed = compile_toml("[[servers]]\nhost = \"a\"\n[[servers.ports]]\nnumber = 1\n", ArrayOfTables::Indexed).unwrap();
+        let tables = &indexed[0].children[0];
+        assert_eq!("{index}", tables.name);
+        assert_eq!(vec!["host", "ports"], tables.children.iter().map(|c| c.name.as_str()).collect::<Vec<&str>>());
+        assert_eq!("{index2}", tables.children[1].children[0].name);
+        assert_eq!("number", tables.children[1].children[0].children[0].name);
+    }
+}
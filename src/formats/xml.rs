@@ -0,0 +1,206 @@
+//! Reader for Android string resources (`strings.xml`) and XML files using the same elements.
+
+use std::ops::Not;
+
+use crate::formats::location_at;
+use crate::{KeyElement, KeygenError};
+
+/// Elements whose `name` attribute is read as a key.
+const RESOURCE_ELEMENTS: &[&str] = &["string", "plurals", "string-array"];
+
+/// A start tag with the byte offset of each attribute value.
+struct StartTag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, String, usize)>,
+}
+
+struct XmlScanner<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlScanner<'a> {
+    fn error(&self, message: &str) -> KeygenError {
+        KeygenError::parse(message, location_at(self.input, self.pos))
+    }
+
+    fn skip_past(&mut self, end: &str, message: &str) -> Result<(), KeygenError> {
+        match self.input[self.pos..].find(end) {
+            Some(index) => {
+                self.pos += index + end.len();
+                Ok(())
+            }
+            None => Err(self.error(message)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn take_name(&mut self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        let length = rest.find(|c: char| c.is_whitespace() || "/>=".contains(c)).unwrap_or(rest.len());
+        self.pos += length;
+        &rest[..length]
+    }
+
+    /// Returns the next start tag. Comments, processing instructions, declarations, end tags and text are skipped.
+    fn next_start_tag(&mut self) -> Result<Option<StartTag<'a>>, KeygenError> {
+        loop {
+            match self.input[self.pos..].find('<') {
+                Some(index) => self.pos += index,
+                None => return Ok(None),
+            }
+
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("<!--") {
+                self.skip_past("-->", "unterminated comment")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>", "unterminated CDATA section")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>", "unterminated processing instruction")?;
+            } else if rest.starts_with("<!") || rest.starts_with("</") {
+                self.skip_past(">", "unterminated tag")?;
+            } else {
+                self.pos += 1;
+                return self.parse_start_tag().map(Some);
+            }
+        }
+    }
+
+    fn parse_start_tag(&mut self) -> Result<StartTag<'a>, KeygenError> {
+        let name = self.take_name();
+        if name.is_empty() {
+            return Err(self.error("expected an element name"));
+        }
+
+        let mut attributes = vec![];
+        loop {
+            self.skip_whitespace();
+            let rest = &self.input[self.pos..];
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(StartTag { name, attributes });
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                return Ok(StartTag { name, attributes });
+            }
+
+            let attribute = self.take_name();
+            self.skip_whitespace();
+            if attribute.is_empty() || self.input[self.pos..].starts_with('=').not() {
+                return Err(self.error("expected an attribute"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+
+            let quote = match self.input[self.pos..].chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            let start = self.pos + 1;
+            let length = self.input[start..].find(quote)
+                .ok_or_else(|| self.error("unterminated attribute value"))?;
+            let value = unescape(&self.input[start..start + length]);
+            attributes.push((attribute, value, start));
+            self.pos = start + length + 1;
+        }
+    }
+}
+
+/// Replaces the predefined entities and character references.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('&') {
+        unescaped.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let replacement = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match replacement {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Reads the `name` attributes of `<string>`, `<plurals>` and `<string-array>` elements.
+///
+/// If `separator` is set, the names are split at it into hierarchy levels.
+pub(crate) fn compile_xml(input: &str, separator: Option<char>) -> Result<Vec<KeyElement>, KeygenError> {
+    let mut root = KeyElement::new("");
+    let mut scanner = XmlScanner { input, pos: 0 };
+    while let Some(tag) = scanner.next_start_tag()? {
+        if RESOURCE_ELEMENTS.contains(&tag.name).not() {
+            continue;
+        }
+        let Some((_, name, offset)) = tag.attributes.into_iter().find(|(a, _, _)| *a == "name") else {
+            continue;
+        };
+
+        let origin = location_at(input, offset);
+        match separator {
+            Some(separator) => {
+                let mut parent = &mut root;
+                let mut origin = origin;
+                for segment in name.split(separator).filter(|s| s.is_empty().not()) {
+                    parent = parent.create_child(segment, &origin);
+                    origin.column += segment.chars().count() + 1;
+                }
+            }
+            None => {
+                root.create_child(&name, &origin);
+            }
+        }
+    }
+    Ok(root.children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SourceLocation;
+
+    #[test]
+    fn android_resources_compile() {
+        let input = include_str!("../test/strings.xml");
+        let keys = compile_xml(input, None).unwrap();
+        let names: Vec<&str> = keys.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(vec!["app_name", "login_title", "login_button", "unread_emails", "planets"], names);
+        assert_eq!(Some(SourceLocation::new(4, 19, "    <string name=\"app_name\">Example &amp; Co</string>")), keys[0].origin);
+
+        let keys = compile_xml(input, Some('_')).unwrap();
+        let names: Vec<&str> = keys.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(vec!["app", "login", "unread", "planets"], names);
+        assert_eq!(2, keys[1].children.len());
+    }
+
+    #[test]
+    fn entities_are_unescaped() {
+        assert_eq!("a&b<c>\"'ä&unknown;", unescape("a&amp;b&lt;c&gt;&quot;&apos;&#xe4;&unknown;"));
+    }
+}
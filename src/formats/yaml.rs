@@ -0,0 +1,212 @@
+//! Reader for YAML documents.
+
+use std::collections::HashMap;
+
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+use crate::{KeyElement, KeygenError, SourceLocation};
+
+/// Name of the merge key, which inserts the keys of other mappings into the current one.
+const MERGE_KEY: &str = "<<";
+
+/// Collections that are currently open while walking the document.
+enum Collection {
+    /// A mapping whose keys are translated to keys below the element at `path`.
+    Mapping {
+        path: Vec<String>,
+        /// The last key, if the next event is its value.
+        pending_value: Option<PendingValue>,
+    },
+    /// A sequence of mappings merged into the element at `path` (`<<: [*a, *b]`).
+    Merge {
+        path: Vec<String>,
+    },
+    /// A sequence or a mapping inside a sequence. Its content is ignored.
+    Ignored,
+}
+
+/// Value of a mapping entry, which is expected as the next event.
+struct PendingValue {
+    /// Path of the element receiving the keys of the value.
+    path: Vec<String>,
+    /// Whether the entry is a merge key, whose value may also be a sequence of mappings.
+    merge: bool,
+}
+
+struct KeyTreeBuilder<'a> {
+    lines: Vec<&'a str>,
+    root: KeyElement,
+    collections: Vec<Collection>,
+    /// Paths of the elements created from anchored mappings, by anchor id.
+    anchors: HashMap<usize, Vec<String>>,
+}
+
+impl KeyTreeBuilder<'_> {
+    fn location(&self, mark: &Marker) -> SourceLocation {
+        let snippet = self.lines.get(mark.line().saturating_sub(1)).copied().unwrap_or_default();
+        SourceLocation::new(mark.line(), mark.col() + 1, snippet)
+    }
+
+    /// Marks the value of the current mapping entry as consumed and returns it.
+    fn take_value(&mut self) -> Option<PendingValue> {
+        match self.collections.last_mut() {
+            Some(Collection::Mapping { pending_value, .. }) => pending_value.take(),
+            _ => None,
+        }
+    }
+
+    /// Copies the keys of the anchored mapping into the element at `path`. Keys the element already has are kept.
+    fn resolve_alias(&mut self, anchor: usize, path: &[String]) {
+        let Some(source) = self.anchors.get(&anchor).and_then(|a| find(&self.root, a)).cloned() else {
+            return;
+        };
+        if let Some(target) = find_mut(&mut self.root, path) {
+            copy_children(&source, target);
+        }
+    }
+}
+
+fn find<'a>(element: &'a KeyElement, path: &[String]) -> Option<&'a KeyElement> {
+    path.iter().try_fold(element, |e, name| e.children.iter().find(|c| &c.name == name))
+}
+
+fn find_mut<'a>(element: &'a mut KeyElement, path: &[String]) -> Option<&'a mut KeyElement> {
+    path.iter().try_fold(element, |e, name| e.children.iter_mut().find(|c| &c.name == name))
+}
+
+fn copy_children(source: &KeyElement, target: &mut KeyElement) {
+    for child in &source.children {
+        match target.children.iter_mut().find(|c| c.name == child.name) {
+            Some(existing) => copy_children(child, existing),
+            None => target.children.push(child.clone()),
+        }
+    }
+}
+
+impl MarkedEventReceiver for KeyTreeBuilder<'_> {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::MappingStart(anchor, _) => {
+                let path = match self.collections.last() {
+                    None => Some(vec![]),
+                    Some(Collection::Mapping { pending_value: Some(_), .. }) => self.take_value().map(|v| v.path),
+                    Some(Collection::Merge { path }) => Some(path.clone()),
+                    // Mappings inside of sequences and complex keys are not part of the key hierarchy.
+                    _ => None,
+                };
+                let collection = match path {
+                    Some(path) => {
+                        if anchor > 0 {
+                            self.anchors.insert(anchor, path.clone());
+                        }
+                        Collection::Mapping { path, pending_value: None }
+                    }
+                    None => Collection::Ignored,
+                };
+                self.collections.push(collection);
+            }
+            Event::SequenceStart(..) => {
+                let collection = match self.take_value() {
+                    Some(PendingValue { path, merge: true }) => Collection::Merge { path },
+                    _ => Collection::Ignored,
+                };
+                self.collections.push(collection);
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.collections.pop();
+            }
+            Event::Scalar(value, ..) => {
+                if self.take_value().is_some() {
+                    return;
+                }
+                let origin = self.location(&mark);
+                if let Some(Collection::Mapping { path, pending_value }) = self.collections.last_mut() {
+                    if value == MERGE_KEY {
+                        *pending_value = Some(PendingValue { path: path.clone(), merge: true });
+                        return;
+                    }
+                    // Names are not split at `.`, the hierarchy is defined by the nesting of the mappings only.
+                    let parent = path.iter().fold(&mut self.root, |element, name| element.create_child(name, &origin));
+                    parent.create_child(&value, &origin);
+                    *pending_value = Some(PendingValue { path: path.iter().cloned().chain([value]).collect(), merge: false });
+                }
+            }
+            Event::Alias(anchor) => {
+                let path = match self.collections.last() {
+                    Some(Collection::Merge { path }) => Some(path.clone()),
+                    _ => self.take_value().map(|v| v.path),
+                };
+                if let Some(path) = path {
+                    self.resolve_alias(anchor, &path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reads the keys of all mappings in the YAML documents.
+///
+/// Keys of nested mappings become child keys, all other values are ignored.
+/// Aliases of mappings and merge keys (`<<`) are resolved to the keys of the anchored mapping.
+pub(crate) fn compile_yaml(input: &str) -> Result<Vec<KeyElement>, KeygenError> {
+    let mut builder = KeyTreeBuilder {
+        lines: input.lines().collect(),
+        root: KeyElement::new(""),
+        collections: vec![],
+        anchors: HashMap::new(),
+    };
+    Parser::new_from_str(input)
+        .load(&mut builder, true)
+        .map_err(|e| KeygenError::parse(e.info().to_string(), builder.location(e.marker())))?;
+    Ok(builder.root.children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::expecded_structure;
+
+    #[test]
+    fn yaml_input_compiles() {
+        let input = include_str!("../test/hierarchical.yaml");
+        assert_eq!(expecded_structure(), compile_yaml(input).unwrap());
+    }
+
+    #[test]
+    fn yaml_keys_record_their_location() {
+        let keys = compile_yaml("server:\n  port: 80\n").unwrap();
+        assert_eq!(Some(SourceLocation::new(2, 3, "  port: 80")), keys[0].children[0].origin);
+    }
+
+    #[test]
+    fn yaml_names_are_not_split() {
+        let keys = compile_yaml("a.b:\n  c: 1\n").unwrap();
+        assert_eq!("a.b", keys[0].name);
+        assert_eq!("c", keys[0].children[0].name);
+    }
+
+    #[test]
+    fn yaml_merge_keys_and_aliases_are_resolved() {
+        let input = "base: &base\n  host: h\n  tls:\n    cert: c\n\
+            prod:\n  <<: *base\n  port: 1\n\
+            both:\n  <<: [*base, {extra: 1}]\n\
+            copy: *base\n";
+        let keys = compile_yaml(input).unwrap();
+        let names = |e: &KeyElement| e.children.iter().map(|c| c.name.clone()).collect::<Vec<String>>();
+        assert_eq!(vec!["base", "prod", "both", "copy"], keys.iter().map(|k| k.name.clone()).collect::<Vec<String>>());
+        assert_eq!(vec!["host", "tls", "port"], names(&keys[1]));
+        assert_eq!(vec!["cert"], names(&keys[1].children[1]));
+        assert_eq!(vec!["host", "tls", "extra"], names(&keys[2]));
+        assert_eq!(vec!["host", "tls"], names(&keys[3]));
+    }
+
+    #[test]
+    fn yaml_syntax_errors_are_reported() {
+        match compile_yaml("a:\n  b: [c\n") {
+            Err(KeygenError::Parse { location, .. }) => assert_eq!(3, location.line),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Expansion of glob patterns for input files.
+//!
+//! Supported are `*` and `?` within a path component and `**` for any number of directories.
+
+use std::fs::read_dir;
+use std::ops::Not;
+use std::path::{Path, PathBuf};
+
+use crate::KeygenError;
+
+/// Returns all files matching the pattern, sorted by path.
+pub(crate) fn expand(pattern: &str) -> Result<Vec<PathBuf>, KeygenError> {
+    let (base, components) = split_base(pattern);
+    let mut files = vec![];
+    collect_matches(&base, &components, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the directory the pattern is matched in, i.e. the leading path components without wildcards.
+pub(crate) fn base_dir(pattern: &str) -> PathBuf {
+    split_base(pattern).0
+}
+
+fn split_base(pattern: &str) -> (PathBuf, Vec<&str>) {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal_count = components.iter()
+        .take_while(|c| c.contains(['*', '?']).not())
+        .count()
+        .min(components.len() - 1);
+
+    let base = match components[..literal_count].join("/") {
+        base if base.is_empty() && pattern.starts_with('/') => PathBuf::from("/"),
+        base if base.is_empty() => PathBuf::from("."),
+        base => PathBuf::from(base),
+    };
+    (base, components[literal_count..].to_vec())
+}
+
+fn collect_matches(dir: &Path, pattern: &[&str], files: &mut Vec<PathBuf>) -> Result<(), KeygenError> {
+    let Some((&component, remaining)) = pattern.split_first() else {
+        return Ok(());
+    };
+    if component == "**" {
+        // `**` matches no directory at all ...
+        collect_matches(dir, remaining, files)?;
+    }
+
+    let entries = match read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(KeygenError::io(dir, e)),
+    };
+    for entry in entries {
+        let entry = entry.map_err(|e| KeygenError::io(dir, e))?;
+        let file_type = entry.file_type().map_err(|e| KeygenError::io(&entry.path(), e))?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        if component == "**" {
+            // ... or any number of directories.
+            if file_type.is_dir() {
+                collect_matches(&entry.path(), pattern, files)?;
+            }
+        } else if matches(component, &name) {
+            if remaining.is_empty() && file_type.is_file() {
+                files.push(entry.path());
+            } else if remaining.is_empty().not() && file_type.is_dir() {
+                collect_matches(&entry.path(), remaining, files)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks whether the name matches the pattern of a single path component.
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches_chars(&pattern, &name)
+}
+
+fn matches_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_chars(rest, &name[skip..])),
+        Some(('?', rest)) => name.split_first().is_some_and(|(_, name)| matches_chars(rest, name)),
+        Some((c, rest)) => name.split_first().is_some_and(|(n, name)| n == c && matches_chars(rest, name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn components_are_matched() {
+        assert!(matches("*.keys", "auth.keys"));
+        assert!(matches("a?th.*", "auth.keys"));
+        assert!(matches("auth.keys", "auth.keys"));
+        assert!(matches("*.keys", "auth.yaml").not());
+    }
+
+    #[test]
+    fn patterns_are_expanded() {
+        let files = expand("src/test/*.keys").unwrap();
+        assert!(files.contains(&PathBuf::from("src/test/hierarchical.keys")));
+        assert!(files.iter().all(|f| f.extension().is_some_and(|e| e == "keys")));
+
+        let files = expand("src/**/hierarchical.json").unwrap();
+        assert_eq!(vec![PathBuf::from("src/test/hierarchical.json")], files);
+        assert_eq!(PathBuf::from("src/test"), base_dir("src/test/*.keys"));
+        assert_eq!(PathBuf::from("."), base_dir("*.keys"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Translation of key segments into rust identifiers and string literals.
+
+use std::ops::Not;
+
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+/// Keywords that have to be emitted as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move",
+    "mut", "override", "priv", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Keywords that can not be used as identifiers at all, not even as raw identifiers.
+const RESERVED: &[&str] = &["self", "Self", "super", "crate", "_"];
+
+/// How key segments that are not valid rust identifiers are handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum IdentifierPolicy {
+    /// Invalid characters are replaced with `_` and segments starting with a digit are prefixed with `_`.
+    Sanitize,
+    /// Invalid segments are reported as an error.
+    Error,
+}
+
+/// Naming convention applied to the identifiers of generated items.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// The key segment is used unchanged (e.g. `httpPort`).
+    AsIs,
+    /// Words are lowercase and separated by `_` (e.g. `http_port`).
+    SnakeCase,
+    /// Words are uppercase and separated by `_` (e.g. `HTTP_PORT`).
+    ScreamingSnakeCase,
+}
+
+/// Converts the key segment to the given case.
+///
+/// Words are separated by non-alphanumeric characters and by changes from lower- to uppercase
+/// (`http-port`, `http_port`, `httpPort` and `HTTPPort` all consist of the words `http` and `port`).
+pub(crate) fn convert_case(segment: &str, case: Case) -> String {
+    let words = split_words(segment);
+    match case {
+        Case::AsIs => segment.to_string(),
+        Case::SnakeCase => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("_"),
+        Case::ScreamingSnakeCase => words.iter().map(|w| w.to_uppercase()).collect::<Vec<_>>().join("_"),
+    }
+}
+
+/// Converts the key segment to the UpperCamelCase name of an enum variant (e.g. `http-port` to `HttpPort`).
+pub(crate) fn variant_name(segment: &str) -> String {
+    split_words(segment).iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next()
+                .map(|first| first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn split_words(segment: &str) -> Vec<String> {
+    let chars: Vec<char> = segment.chars().collect();
+    let mut words = vec![];
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_alphanumeric().not() {
+            if current.is_empty().not() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && current.is_empty().not() {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if previous.is_lowercase() || previous.is_numeric() || (previous.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if current.is_empty().not() {
+        words.push(current);
+    }
+    words
+}
+
+/// Returns the rust identifier for the given key segment.
+///
+/// Keywords are emitted as raw identifiers (e.g. `r#type`).
+/// Returns an error message if the segment can not be represented with the given policy.
+pub(crate) fn to_identifier(segment: &str, policy: IdentifierPolicy) -> Result<String, String> {
+    if segment.is_empty() {
+        return Err("empty key segment can not be used as an identifier".to_string());
+    }
+
+    let valid = segment.chars().enumerate()
+        .all(|(i, c)| if i == 0 { c == '_' || is_xid_start(c) } else { is_xid_continue(c) });
+    let ident = if valid {
+        segment.to_string()
+    } else {
+        match policy {
+            IdentifierPolicy::Sanitize => sanitize(segment),
+            IdentifierPolicy::Error => {
+                return Err(format!("key segment `{}` is not a valid rust identifier", segment));
+            }
+        }
+    };
+
+    if RESERVED.contains(&ident.as_str()) {
+        Err(format!("key segment `{}` can not be used as an identifier, because `{}` is a reserved keyword", segment, ident))
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        Ok(format!("r#{}", ident))
+    } else {
+        Ok(ident)
+    }
+}
+
+fn sanitize(segment: &str) -> String {
+    let replaced: String = segment.chars()
+        .map(|c| if is_xid_continue(c) { c } else { '_' })
+        .collect();
+    if replaced.starts_with(|c: char| c != '_' && is_xid_start(c).not()) {
+        format!("_{}", replaced)
+    } else {
+        replaced
+    }
+}
+
+/// Returns the given value as a rust string literal, including the quotes.
+pub(crate) fn string_literal(value: &str) -> String {
+    // The debug representation of `str` uses the same escapes as rust string literals.
+    format!("{:?}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_are_translated_to_identifiers() {
+        let policy = IdentifierPolicy::Sanitize;
+        assert_eq!(Ok("port".to_string()), to_identifier("port", policy));
+        assert_eq!(Ok("r#type".to_string()), to_identifier("type", policy));
+        assert_eq!(Ok("http_port".to_string()), to_identifier("http-port", policy));
+        assert_eq!(Ok("_2fa".to_string()), to_identifier("2fa", policy));
+        assert_eq!(Ok("größe".to_string()), to_identifier("größe", policy));
+        assert_eq!(Ok("a_".to_string()), to_identifier("a½", policy));
+        assert_eq!(Ok("x_".to_string()), to_identifier("x²", policy));
+        assert!(to_identifier("x²", IdentifierPolicy::Error).is_err());
+        assert!(to_identifier("self", policy).is_err());
+        assert!(to_identifier("-", policy).is_err());
+        assert!(to_identifier("http-port", IdentifierPolicy::Error).is_err());
+    }
+
+    #[test]
+    fn case_is_converted() {
+        assert_eq!("http_port", convert_case("httpPort", Case::SnakeCase));
+        assert_eq!("HTTP_PORT", convert_case("http-port", Case::ScreamingSnakeCase));
+        assert_eq!("http_server", convert_case("HTTPServer", Case::SnakeCase));
+        assert_eq!("http-port", convert_case("http-port", Case::AsIs));
+        assert_eq!("HttpServer", variant_name("HTTPServer"));
+        assert_eq!("MaxConnections", variant_name("max_connections"));
+    }
+
+    #[test]
+    fn values_are_escaped() {
+        assert_eq!(r#""say \"hi\"\\now""#, string_literal("say \"hi\"\\now"));
+    }
+}
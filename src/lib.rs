@@ -1,67 +1,544 @@
 //! # Keystring generator
 //! This package is intended to be used in cargo build-scripts.
 //! It can be used to generate constant strings, that are used as keys in maps, configurations, etc.
+//!
+//! A typical `build.rs` looks like this:
+//! ```no_run
+//! keystring_generator::Config::new()
+//!     .input("keys/config.keys")
+//!     .generate()
+//!     .unwrap();
+//! ```
+//!
+//! The generated file is written to cargo's `OUT_DIR` and may be included with [`include_keys!`]
+//! or `include!(concat!(env!("OUT_DIR"), "/keygen.rs"));`.
 
-use std::fs::{create_dir_all, File};
-use std::io::{Read, Write};
 use std::ops::Not;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub use config::{Config, KeyTable, Visibility};
+pub use error::{KeygenError, SourceLocation};
+pub use formats::{ArrayOfTables, InputFormat};
+pub use ident::{Case, IdentifierPolicy};
+
+mod config;
+mod error;
+mod formats;
+mod glob;
+mod ident;
+mod lookup;
+
+#[derive(Clone, Debug)]
 struct KeyElement {
     name: String,
+    doc: Option<String>,
+    /// Explicit key string, overriding the string computed from the path of the element.
+    value: Option<String>,
+    /// Separator between this element and its descendants, overriding the inherited one.
+    separator: Option<String>,
+    /// Declared type of the value configured under this key. Only used as metadata in the lookup table.
+    value_type: Option<String>,
     children: Vec<KeyElement>,
+    /// Location where the element was defined first.
+    origin: Option<SourceLocation>,
+}
+
+// Where a key was defined is not part of its identity.
+impl PartialEq for KeyElement {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.doc == other.doc && self.value == other.value && self.separator == other.separator
+            && self.value_type == other.value_type && self.children == other.children
+    }
 }
 
+impl Eq for KeyElement {}
+
 impl KeyElement {
-    fn create_key(&mut self, key: &str) {
-        let (key, remaining) = key.split_once(".").unwrap_or((key, ""));
+    fn new(name: &str) -> KeyElement {
+        KeyElement {
+            name: name.to_string(),
+            doc: None,
+            value: None,
+            separator: None,
+            value_type: None,
+            children: vec![],
+            origin: None,
+        }
+    }
+
+    /// Creates the (`.`-separated) key below this element, if it does not exist yet, and returns its last element.
+    ///
+    /// `origin` points to the start of `key` in the input and is recorded for all newly created elements.
+    fn create_key(&mut self, key: &str, origin: &SourceLocation) -> &mut KeyElement {
+        let (key, remaining) = key.split_once('.').unwrap_or((key, ""));
 
-        if self.children.iter().any(|c| c.name == key).not() {
-            let mut child = KeyElement {
-                name: key.to_string(),
-                children: vec![],
+        let child = self.create_child(key, origin);
+        if remaining.is_empty() {
+            child
+        } else {
+            let mut remaining_origin = origin.clone();
+            remaining_origin.column += key.chars().count() + 1;
+            child.create_key(remaining, &remaining_origin)
+        }
+    }
+
+    /// Returns the direct child with the given name, creating it if it does not exist yet.
+    /// In contrast to [`KeyElement::create_key`] the name is not split at `.`.
+    fn create_child(&mut self, name: &str, origin: &SourceLocation) -> &mut KeyElement {
+        let index = match self.children.iter().position(|c| c.name == name) {
+            Some(index) => index,
+            None => {
+                let mut child = KeyElement::new(name);
+                child.origin = Some(origin.clone());
+                self.children.push(child);
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[index]
+    }
+
+    /// Creates `key` below the element at the (`.`-separated) path `parent` and returns its last element.
+    /// If `parent` is empty, the key is created directly below this element.
+    fn create_key_below(&mut self, parent: &str, key: &str, origin: &SourceLocation) -> &mut KeyElement {
+        let parent = if parent.is_empty() {
+            self
+        } else {
+            self.create_key(parent, origin)
+        };
+        parent.create_key(key, origin)
+    }
+
+    /// Attaches the path of the input file to the origins of this element and all its descendants, unless they already have one.
+    fn set_path(&mut self, path: &Path) {
+        if let Some(origin) = self.origin.as_mut().filter(|o| o.path.is_none()) {
+            origin.path = Some(path.to_path_buf());
+        }
+        self.children.iter_mut().for_each(|c| c.set_path(path));
+    }
+
+    /// Merges the children of `other` (e.g. compiled from another input file) into the children of this element.
+    ///
+    /// Elements with the same name are merged recursively. `parent` is the key string of this element and is only
+    /// used in error messages.
+    fn merge(&mut self, other: KeyElement, parent: &str) -> Result<(), KeygenError> {
+        for child in other.children {
+            let Some(existing) = self.children.iter_mut().find(|c| c.name == child.name) else {
+                self.children.push(child);
+                continue;
             };
 
-            if remaining.is_empty().not() {
-                child.create_key(remaining);
+            let key = if parent.is_empty() {
+                child.name.clone()
+            } else {
+                format!("{}.{}", parent, child.name)
+            };
+            let conflict = if merge_attribute(&mut existing.doc, &child.doc).not() {
+                Some("is documented differently")
+            } else if merge_attribute(&mut existing.value, &child.value).not() {
+                Some("has different values")
+            } else if merge_attribute(&mut existing.separator, &child.separator).not() {
+                Some("has different separators")
+            } else if merge_attribute(&mut existing.value_type, &child.value_type).not() {
+                Some("has different types")
+            } else {
+                None
+            };
+            if let Some(conflict) = conflict {
+                return Err(KeygenError::Validation {
+                    message: format!("the key `{}` {} in multiple inputs", key, conflict),
+                    location: child.origin.clone(),
+                    notes: existing.origin.iter().map(|o| (format!("`{}` is also defined here", key), o.clone())).collect(),
+                });
             }
+            existing.merge(child, &key)?;
+        }
+        Ok(())
+    }
 
-            self.children.push(child);
-        } else if remaining.is_empty().not() {
-            let children = &mut self.children;
-            children.iter_mut()
-                .find(|c| c.name == key)
-                .unwrap()
-                .create_key(remaining)
+    /// Sets the explicit value or an attribute of this element, selected by `field`.
+    /// Returns an error if it is already set to something different. `what` names the field in the error message.
+    fn assign(
+        &mut self,
+        what: &str,
+        field: impl FnOnce(&mut KeyElement) -> &mut Option<String>,
+        value: String,
+        origin: &SourceLocation,
+    ) -> Result<(), KeygenError> {
+        if merge_attribute(field(self), &Some(value)).not() {
+            return Err(KeygenError::Validation {
+                message: format!("the key `{}` has different {}", self.name, what),
+                location: Some(origin.clone()),
+                notes: self.origin.iter().map(|o| (format!("`{}` is also defined here", self.name), o.clone())).collect(),
+            });
         }
+        Ok(())
     }
 
-    fn generate_code(&self, separator: &str, parent: &str) -> Result<String, String> {
-        let parent_string: String;
-        if parent.is_empty() {
-            parent_string = self.name.to_string();
+    fn doc_comment(&self) -> String {
+        self.doc.as_ref()
+            .map(|doc| doc.lines().fold("\n".to_string(), |acc, l| format!("{}/// {}\n", acc, l)))
+            .unwrap_or_default()
+    }
+
+    /// Returns the name of the placeholder, if this element is a placeholder segment (`{name}`).
+    fn placeholder(&self) -> Option<&str> {
+        self.name.strip_prefix('{')?.strip_suffix('}')
+    }
+
+    /// Returns the identifier of the item generated for this element.
+    ///
+    /// Below placeholders (`templated`) functions are generated instead of constants, which follow the naming convention of modules.
+    fn identifier(&self, config: &Config, templated: bool) -> Result<String, KeygenError> {
+        let case = if self.children.is_empty() && templated.not() {
+            config.constant_case
         } else {
-            parent_string = format!("{}{}{}", parent, separator, self.name);
+            config.module_case
+        };
+        let name = self.placeholder().unwrap_or(&self.name);
+        ident::to_identifier(&ident::convert_case(name, case), config.identifier_policy)
+            .map_err(|message| KeygenError::Codegen { message, location: self.origin.clone() })
+    }
+
+    /// Whether this module contains keys without placeholders, so it gets an enum `Key` with at least one variant.
+    fn has_static_keys(&self) -> bool {
+        self.children.iter()
+            .filter(|c| c.placeholder().is_none())
+            .any(|c| c.children.is_empty() || c.has_static_keys())
+    }
+
+    /// Returns the identifier of the variant generated for this element in the enum `Key` of its module.
+    fn variant(&self, config: &Config) -> Result<String, KeygenError> {
+        ident::to_identifier(&ident::variant_name(&self.name), config.identifier_policy)
+            .map_err(|message| KeygenError::Codegen { message, location: self.origin.clone() })
+    }
+
+    /// Returns the identifier of the parameter generated for this placeholder element.
+    /// Invalid names are always rejected, as they would not be recognizable in the generated signature.
+    fn parameter(&self, name: &str, config: &Config) -> Result<String, KeygenError> {
+        ident::to_identifier(&ident::convert_case(name, config.module_case), IdentifierPolicy::Error)
+            .map_err(|message| KeygenError::Codegen { message, location: self.origin.clone() })
+    }
+
+    /// Generates the items for this element. `separator` is the separator between `parent` and this element.
+    /// `constants` are the identifiers of the constants in the module the items are generated into.
+    ///
+    /// Placeholders with children do not generate an item themselves, the items of their children are returned instead.
+    /// The key strings of all elements without placeholders are added to `table`.
+    fn generate_code<'a>(
+        &'a self,
+        config: &Config,
+        parent: &[Segment],
+        separator: &str,
+        constants: &[String],
+        table: &mut Vec<TableEntry<'a>>,
+    ) -> Result<Vec<String>, KeygenError> {
+        let mut path = parent.to_vec();
+        if parent.is_empty().not() {
+            path.push(Segment::Text(separator.to_string()));
         }
-        if self.children.is_empty() {
-            Ok(format!("pub const {}: &str = \"{}\";", self.name, parent_string))
+        match self.placeholder() {
+            Some(name) => path.push(Segment::Placeholder(self.parameter(name, config)?)),
+            None => path.push(Segment::Text(self.name.to_string())),
+        }
+
+        let templated = path.iter().any(|s| matches!(s, Segment::Placeholder(_)));
+        // Placeholders are transparent, the items of their children are generated into the enclosing module.
+        let child_constants = if self.placeholder().is_some() {
+            constants.to_vec()
         } else {
-            let child_generated = self.children
-                .iter()
-                .map(|c| c.generate_code(separator, &parent_string).unwrap())
+            let mut child_constants = module_constants(&self.children, config)?;
+            if templated.not() {
+                child_constants.push("_BASE".to_string());
+            }
+            child_constants
+        };
+        let child_separator = self.separator.as_deref().unwrap_or(separator);
+        let mut child_generated = vec![];
+        for child in &self.children {
+            child_generated.extend(child.generate_code(config, &path, child_separator, &child_constants, table)?);
+        }
+        if self.placeholder().is_some() && self.children.is_empty().not() {
+            return Ok(child_generated);
+        }
+
+        let params = path.iter()
+            .filter_map(|s| match s {
+                Segment::Placeholder(param) => Some(param.as_str()),
+                Segment::Text(_) => None,
+            })
+            .collect::<Vec<&str>>();
+        let ident = self.identifier(config, params.is_empty().not())?;
+        let vis = config.visibility.keyword();
+        let base = if params.is_empty() {
+            let text = path.iter().map(Segment::text).collect::<String>();
+            let text = self.value.clone().unwrap_or(text);
+            // Names and separators alternate in the path.
+            let segments = path.iter().step_by(2).map(|s| s.text().to_string()).collect();
+            let mut value = ident::string_literal(&text);
+            // The newtype is declared at the top level, which is reached from every module by going up once per module.
+            // `_BASE` is declared inside the module of this element, constants inside the module of the parent.
+            let key_type = if config.typed_keys {
+                let modules = parent.iter().step_by(2).filter(|s| matches!(s, Segment::Text(_))).count();
+                let depth = if self.children.is_empty() { modules } else { modules + 1 };
+                let key_type = format!("{}Key", "super::".repeat(depth));
+                value = format!("{}::new({})", key_type, value);
+                key_type
+            } else {
+                "&str".to_string()
+            };
+            table.push(TableEntry { key: text, segments, element: self });
+            if self.children.is_empty() {
+                format!("{} const {}: {} = {};", vis, ident, key_type, value)
+            } else {
+                format!("{} const _BASE : {} = {};", vis, key_type, value)
+            }
+        } else {
+            let names = parameter_names(&params, if self.children.is_empty() { constants } else { &child_constants });
+            let signature = names.iter()
+                .map(|p| format!("{}: impl ::std::fmt::Display", p))
                 .collect::<Vec<String>>()
-                .join("");
-            Ok(format!("pub mod {} {{pub const _BASE : &str = \"{}\";{} }}", self.name, parent_string, child_generated))
+                .join(", ");
+            let template = ident::string_literal(&path.iter().map(Segment::template).collect::<String>());
+            let allow = if self.children.is_empty() { "" } else { "#[allow(non_snake_case)] " };
+            let function = if self.children.is_empty() { &ident } else { "_BASE" };
+            format!(
+                "{}{} fn {}({}) -> ::std::string::String {{ ::std::format!({}, {}) }}",
+                allow, vis, function, signature, template, names.join(", "),
+            )
+        };
+
+        if self.children.is_empty() {
+            Ok(vec![format!("{}{}", self.doc_comment(), base)])
+        } else {
+            if config.key_enums && params.is_empty() && self.has_static_keys() {
+                child_generated.push(key_enum(&self.children, &child_constants, config)?);
+            }
+            Ok(vec![format!("{}{} mod {} {{{}{} }}", self.doc_comment(), vis, ident, base, child_generated.join(""))])
         }
     }
 }
 
-/// Generates rust source code from the given input file and saves it to the file `generated/keygen/keygen.rs`.
+/// Returns the identifiers of the constants generated for `keys` into their module, excluding `_BASE`.
+///
+/// Parameters and bindings in the generated code must not be named like one of them, as the name would refer to the constant.
+fn module_constants(keys: &[KeyElement], config: &Config) -> Result<Vec<String>, KeygenError> {
+    let mut items = vec![];
+    module_items(keys, &[], config, &mut items)?;
+    items.into_iter()
+        .filter(|(key, placeholders)| key.children.is_empty() && placeholders.is_empty())
+        .map(|(key, _)| key.identifier(config, false))
+        .collect()
+}
+
+/// Returns `name`, extended with `_` until it is not contained in `taken`.
+fn unused_name(name: &str, taken: &[String]) -> String {
+    let mut name = name.to_string();
+    while taken.contains(&name) {
+        name.push('_');
+    }
+    name
+}
+
+/// Returns the names of the parameters of a function generated next to `constants`, one for each of the placeholders `params`.
+/// Parameters named like a constant are renamed, the names of the other parameters are kept.
+fn parameter_names(params: &[&str], constants: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for param in params {
+        let name = if constants.iter().any(|c| c == param) {
+            let taken = constants.iter().cloned()
+                .chain(params.iter().map(|p| p.to_string()))
+                .chain(names.iter().cloned())
+                .collect::<Vec<String>>();
+            unused_name(param, &taken)
+        } else {
+            param.to_string()
+        };
+        names.push(name);
+    }
+    names
+}
+
+/// Key string of an element without placeholders, collected while generating the code.
+struct TableEntry<'a> {
+    key: String,
+    /// Names of the elements in the path of the key.
+    segments: Vec<String>,
+    element: &'a KeyElement,
+}
+
+/// Generates the newtype `Key` used as type of the constants with [`Config::typed_keys`].
+/// `constants` are the identifiers of the constants at the top level.
+///
+/// `Hash`, `Eq` and `Ord` are derived from the wrapped `&str`, so they are compatible with `str` as required by `Borrow<str>`.
+/// It is a braced struct, as the constructor of a tuple struct would collide with a constant named `Key`.
+fn key_newtype(constants: &[String], config: &Config) -> [String; 6] {
+    let vis = config.visibility.keyword();
+    [
+        format!("#[repr(transparent)] #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)] {} struct Key {{ key: &'static str }}", vis),
+        format!(
+            "impl Key {{ pub(crate) const fn new({key}: &'static str) -> Key {{ Key {{ key: {key} }} }} \
+            {} const fn as_str(&self) -> &'static str {{ self.key }} }}",
+            vis,
+            key = unused_name("key", constants),
+        ),
+        "impl ::std::ops::Deref for Key { type Target = str; fn deref(&self) -> &str { self.key } }".to_string(),
+        "impl ::std::convert::AsRef<str> for Key { fn as_ref(&self) -> &str { self.key } }".to_string(),
+        "impl ::std::borrow::Borrow<str> for Key { fn borrow(&self) -> &str { self.key } }".to_string(),
+        format!(
+            "impl ::std::fmt::Display for Key {{ fn fmt(&self, {f}: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{ {f}.write_str(self.key) }} }}",
+            f = unused_name("f", constants),
+        ),
+    ]
+}
+
+/// Generates the sorted table `ALL_KEYS` of the given keys and the function `is_known_key` searching it.
 ///
-/// This function generates the code with a standard configuration. For examples and more configuration options see `generate_with_config`.
-pub fn generate(input: &PathBuf) -> Result<(), String> {
-    generate_with_config(input, None, false, ".")
+/// The `_BASE` keys of modules are only included if configured with [`Config::key_table`].
+/// `constants` are the identifiers of the constants at the top level.
+fn key_table(table: &[TableEntry], constants: &[String], config: &Config) -> [String; 2] {
+    let mut keys = table.iter()
+        .filter(|e| e.element.children.is_empty() || config.key_table == KeyTable::LeavesAndBases)
+        .map(|e| e.key.as_str())
+        .collect::<Vec<&str>>();
+    keys.sort();
+    keys.dedup();
+    let vis = config.visibility.keyword();
+    let keys = keys.iter().map(|k| ident::string_literal(k)).collect::<Vec<String>>();
+    [
+        format!("{} const ALL_KEYS: &[&str] = &[{}];", vis, keys.join(", ")),
+        format!("{} fn is_known_key({key}: &str) -> bool {{ ALL_KEYS.binary_search(&{key}).is_ok() }}", vis, key = unused_name("key", constants)),
+    ]
+}
+
+/// Generates the enum `Key` of the module containing `keys`. `constants` are the identifiers of the constants in the module.
+///
+/// Leaves become unit variants, modules become variants wrapping the enum of the module. Placeholders are skipped,
+/// as the keys below them are not static, and so are modules without static keys.
+fn key_enum(keys: &[KeyElement], constants: &[String], config: &Config) -> Result<String, KeygenError> {
+    let vis = config.visibility.keyword();
+    // Bindings in the generated code must not be named like a constant of the module.
+    let (inner, f, s, k) = (unused_name("key", constants), unused_name("f", constants), unused_name("s", constants), unused_name("k", constants));
+    let mut variants = vec![];
+    let mut arms = vec![];
+    for key in keys.iter().filter(|k| k.placeholder().is_none() && (k.children.is_empty() || k.has_static_keys())) {
+        let variant = key.variant(config)?;
+        let ident = key.identifier(config, false)?;
+        if key.children.is_empty() {
+            let as_str = if config.typed_keys { ".as_str()" } else { "" };
+            arms.push(format!("Key::{} => {}{},", variant, ident, as_str));
+            variants.push(variant);
+        } else {
+            arms.push(format!("Key::{}({}) => {}.as_str(),", variant, inner, inner));
+            variants.push(format!("{}({}::Key)", variant, ident));
+        }
+    }
+    let all = enum_entries(keys, config, "")?;
+
+    Ok(format!(
+        "#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)] #[allow(clippy::enum_variant_names)] {vis} enum Key {{ {variants} }}\
+        impl Key {{ {vis} const ALL: &'static [Key] = &[{all}]; \
+        {vis} fn as_str(&self) -> &'static str {{ match *self {{ {arms} }} }} }}\
+        impl ::std::fmt::Display for Key {{ fn fmt(&self, {f}: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{ {f}.write_str(self.as_str()) }} }}\
+        impl ::std::str::FromStr for Key {{ type Err = (); \
+        fn from_str({s}: &str) -> ::std::result::Result<Key, ()> {{ Key::ALL.iter().copied().find(|{k}| {k}.as_str() == {s}).ok_or(()) }} }}\
+        impl ::std::convert::TryFrom<&str> for Key {{ type Error = (); fn try_from({s}: &str) -> ::std::result::Result<Key, ()> {{ {s}.parse() }} }}",
+        vis = vis,
+        f = f,
+        s = s,
+        k = k,
+        variants = variants.join(", "),
+        all = all.join(", "),
+        arms = arms.join(" "),
+    ))
+}
+
+/// Returns the expressions of all variants of the enum `Key` of the module containing `keys`, including the variants
+/// of nested modules. `prefix` is the path from the module the expressions are used in to the module containing `keys`.
+fn enum_entries(keys: &[KeyElement], config: &Config, prefix: &str) -> Result<Vec<String>, KeygenError> {
+    let mut entries = vec![];
+    for key in keys.iter().filter(|k| k.placeholder().is_none()) {
+        let variant = key.variant(config)?;
+        if key.children.is_empty() {
+            entries.push(format!("{}Key::{}", prefix, variant));
+        } else {
+            let module_prefix = format!("{}{}::", prefix, key.identifier(config, false)?);
+            for entry in enum_entries(&key.children, config, &module_prefix)? {
+                entries.push(format!("{}Key::{}({})", prefix, variant, entry));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Part of a generated key string.
+#[derive(Clone, Debug)]
+enum Segment {
+    /// Text that is part of the key string as is.
+    Text(String),
+    /// Placeholder, which is filled in at runtime by the parameter with the given identifier.
+    Placeholder(String),
+}
+
+impl Segment {
+    fn text(&self) -> &str {
+        match self {
+            Segment::Text(text) => text,
+            Segment::Placeholder(_) => "",
+        }
+    }
+
+    /// Returns the segment as part of a format string.
+    fn template(&self) -> String {
+        match self {
+            Segment::Text(text) => text.replace('{', "{{").replace('}', "}}"),
+            Segment::Placeholder(_) => "{}".to_string(),
+        }
+    }
+}
+
+/// Adopts `other` if `existing` is not set. Returns `false` if both are set to different values.
+fn merge_attribute(existing: &mut Option<String>, other: &Option<String>) -> bool {
+    match (&existing, other) {
+        (Some(existing), Some(other)) => existing == other,
+        (None, Some(_)) => {
+            existing.clone_from(other);
+            true
+        }
+        _ => true,
+    }
+}
+
+/// Generates rust source code from the given input file and saves it to the file `keygen.rs` in the default output directory.
+///
+/// This function generates the code with a standard configuration. For examples and more configuration options see [`Config`].
+#[deprecated(since = "0.2.0", note = "use `Config::new().input(..).generate()` instead")]
+// The parameter types of earlier versions are kept, so existing calls still compile.
+#[allow(clippy::ptr_arg)]
+pub fn generate(input: &PathBuf) -> Result<(), KeygenError> {
+    Config::new().input(input).generate()
+}
+
+/// Includes the code generated by a build-script into the current module.
+///
+/// Without arguments `$OUT_DIR/keygen.rs` is included. If a different file name was configured with
+/// [`Config::output_file_name`], it has to be passed to the macro:
+/// ```ignore
+/// mod constants {
+///     keystring_generator::include_keys!();
+/// }
+///
+/// mod messages {
+///     keystring_generator::include_keys!("messages.rs");
+/// }
+/// ```
+#[macro_export]
+macro_rules! include_keys {
+    () => {
+        include!(concat!(env!("OUT_DIR"), "/keygen.rs"));
+    };
+    ($file:literal) => {
+        include!(concat!(env!("OUT_DIR"), "/", $file));
+    };
 }
 
 /// Generates rust source code from the given input file.
@@ -71,59 +548,297 @@ pub fn generate(input: &PathBuf) -> Result<(), String> {
 ///  * `input` - Path to the input file in any format as specified in `README.md`
 ///  * `output_dir` - Directory where the output file is generated. The output file will alyways be named `keygen.rs`.
 ///    The necessary directories will be created.
-///    If `None` is supplied the default value (`generated/keygen`) will be used.
+///    If `None` is supplied the default value (`$OUT_DIR` in build-scripts, `generated/keygen` otherwise) will be used.
 ///  * `enable_warnings` - Whether the generated code should trigger warnings, like naming-conventions or unused code. If set to `false`, those warnings will be ignored.
 ///  * `separator` - Separator to use in the generated constants (e.g. `"."`, `":"`, `"/"`).
+///
+/// # Errors
+/// Returns a [`KeygenError`] if the input file can not be read or parsed, or if the output can not be written.
+#[deprecated(since = "0.2.0", note = "use `Config` instead")]
+// The parameter types of earlier versions are kept, so existing calls still compile.
+#[allow(clippy::ptr_arg)]
 pub fn generate_with_config(
     input: &PathBuf,
     output_dir: Option<&PathBuf>,
     enable_warnings: bool,
     separator: &str,
-) -> Result<(), String> {
-    let mut input_file = File::open(input.as_path()).unwrap();
-    let mut input_str = "".to_string();
-    input_file.read_to_string(&mut input_str).unwrap();
-
-    let compiled = compile_input(&input_str).unwrap();
-    let output = compiled.iter()
-        .map(|k| k.generate_code(separator, "").unwrap())
-        .collect::<Vec<String>>()
-        .join("\n");
-
-    let control_macros: &str;
-    if enable_warnings {
-        control_macros = "";
-    } else {
-        control_macros = "#[allow(dead_code)]\n#[allow(non_upper_case_globals)]\n#[allow(non_snake_case)]\n";
-    }
-
-    let default_pathbuf = PathBuf::new().join("generated/keygen");
-    let out_path = output_dir
-        .unwrap_or(&default_pathbuf);
-    create_dir_all(out_path.as_path()).unwrap();
-    let mut out_file = File::create(out_path.join("keygen.rs")).unwrap();
-    out_file.write_all(control_macros.as_bytes()).unwrap();
-    out_file.write_all(output.as_bytes()).unwrap();
+) -> Result<(), KeygenError> {
+    let mut config = Config::new()
+        .input(input)
+        .enable_warnings(enable_warnings)
+        .separator(separator);
+    if let Some(output_dir) = output_dir {
+        config = config.output_dir(output_dir);
+    }
+    config.generate()
+}
+
+/// Generates rust source code from the given input and returns it instead of writing it to a file.
+///
+/// The input is expected in the format configured with [`Config::input_format`] (by default the key format specified in `README.md`).
+/// This is useful if the code should be post-processed or emitted by a procedural macro.
+///
+/// ```
+/// use keystring_generator::{generate_from_str, Config};
+///
+/// let code = generate_from_str("server\n  port", &Config::new()).unwrap();
+/// assert!(code.contains("pub const port: &str = \"server.port\";"));
+/// ```
+///
+/// # Errors
+/// Returns a [`KeygenError`] if the input can not be parsed or translated to rust code.
+/// Source locations in the error do not contain a path.
+///
+/// Files referenced with `@include` are resolved relative to the current working directory.
+pub fn generate_from_str(input: &str, config: &Config) -> Result<String, KeygenError> {
+    let compiled = config.input_format.unwrap_or(InputFormat::Keys).compile(input, None, config, &mut vec![])?;
+    generate_from_keys(compiled, config)
+}
+
+/// Generates rust source code from the compiled key tree.
+fn generate_from_keys(mut compiled: Vec<KeyElement>, config: &Config) -> Result<String, KeygenError> {
+    if config.sort_keys {
+        sort_keys(&mut compiled);
+    }
+    if config.typed_keys && config.key_enums && config.root_module.is_some() {
+        return Err(KeygenError::validation("typed keys and key enums both generate a type `Key` in the root module", None));
+    }
+    let root_module = config.root_module.as_deref()
+        .map(|name| {
+            ident::to_identifier(name, IdentifierPolicy::Error)
+                .map_err(|_| KeygenError::validation(format!("the root module `{}` is not a valid rust identifier", name), None))
+        })
+        .transpose()?;
+    validate_identifiers(&compiled, None, &[], config)?;
+    let mut constants = module_constants(&compiled, config)?;
+    if config.key_table != KeyTable::None {
+        constants.push("ALL_KEYS".to_string());
+    }
+    let mut output = vec![];
+    if config.typed_keys {
+        output.extend(key_newtype(&constants, config));
+    }
+    let mut table = vec![];
+    for key in &compiled {
+        output.extend(key.generate_code(config, &[], &config.separator, &constants, &mut table)?);
+    }
+    if config.key_table != KeyTable::None {
+        output.extend(key_table(&table, &constants, config));
+    }
+    if config.key_lookup {
+        output.extend(lookup::generate(&table, &constants, config)?);
+    }
+
+    // Attributes only apply to the item following them, so they are repeated for every top level item.
+    let control_macros = control_macros(config);
+    Ok(match root_module {
+        Some(root_module) => {
+            if config.key_enums {
+                output.push(key_enum(&compiled, &constants, config)?);
+            }
+            format!("{}{} mod {} {{\n{}\n}}", control_macros, config.visibility.keyword(), root_module, output.join("\n"))
+        }
+        None => {
+            // Doc comments start on a new line, which would separate them from the attributes.
+            output.iter()
+                .map(|item| format!("{}{}", control_macros, item.trim_start_matches('\n')))
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+    })
+}
+
+/// Checks that no two items generated into the same module share an identifier.
+///
+/// Constants and modules live in different namespaces, so a constant may have the same name as a module.
+/// `placeholders` are the names of the placeholders in the path of `keys`.
+fn validate_identifiers(keys: &[KeyElement], parent: Option<&KeyElement>, placeholders: &[&str], config: &Config) -> Result<(), KeygenError> {
+    let mut items = vec![];
+    module_items(keys, placeholders, config, &mut items)?;
+
+    // The top level keys only get an enum inside the root module.
+    let has_enum = config.key_enums && placeholders.is_empty() && (parent.is_some() || config.root_module.is_some());
+    // Modules only get an enum if they contain static keys, the root module always gets one.
+    let generates_enum = has_enum && parent.is_none_or(KeyElement::has_static_keys);
+    let mut generated: Vec<(String, bool, &KeyElement)> = vec![];
+    let mut variants: Vec<(String, &KeyElement)> = vec![];
+    for (key, placeholders) in items {
+        let templated = placeholders.is_empty().not();
+        let ident = key.identifier(config, templated)?;
+        let is_module = key.children.is_empty().not();
+
+        if has_enum && templated.not() && (is_module.not() || key.has_static_keys()) {
+            let variant = key.variant(config)?;
+            if let Some((_, other)) = variants.iter().find(|(v, _)| *v == variant) {
+                return Err(KeygenError::Validation {
+                    message: format!("the keys `{}` and `{}` are both generated as the variant `Key::{}`", other.name, key.name, variant),
+                    location: key.origin.clone(),
+                    notes: other.origin.iter().map(|o| (format!("`{}` is defined here", other.name), o.clone())).collect(),
+                });
+            }
+            variants.push((variant, key));
+        }
+        if generates_enum && is_module && ident == "Key" {
+            return Err(KeygenError::validation(
+                format!("the module of the key `{}` collides with the generated enum `Key`", key.name),
+                key.origin.clone(),
+            ));
+        }
+
+        if let Some((_, _, other)) = generated.iter().find(|(i, m, _)| *i == ident && *m == is_module) {
+            return Err(KeygenError::Validation {
+                message: format!("the keys `{}` and `{}` are both generated as `{}`", other.name, key.name, ident),
+                location: key.origin.clone(),
+                notes: other.origin.iter().map(|o| (format!("`{}` is defined here", other.name), o.clone())).collect(),
+            });
+        }
+        if parent.is_none() && top_level_items(config).contains(&(ident.as_str(), is_module)) {
+            return Err(KeygenError::validation(
+                format!("the key `{}` collides with the generated `{}`", key.name, ident),
+                key.origin.clone(),
+            ));
+        }
+        if let Some(parent) = parent.filter(|_| is_module.not() && ident == "_BASE") {
+            return Err(KeygenError::Validation {
+                message: format!("the key `{}` collides with the generated constant `_BASE` of `{}`", key.name, parent.name),
+                location: key.origin.clone(),
+                notes: parent.origin.iter().map(|o| (format!("`{}` is defined here", parent.name), o.clone())).collect(),
+            });
+        }
+        if templated && key.value.is_some() {
+            return Err(KeygenError::validation(
+                format!("the key `{}` is below a placeholder and can not have an explicit value", key.name),
+                key.origin.clone(),
+            ));
+        }
+
+        validate_identifiers(&key.children, Some(key), &placeholders, config)?;
+        generated.push((ident, is_module, key));
+    }
+    Ok(())
+}
+
+/// Returns the identifiers of the items generated at the top level besides the keys, and whether they collide with modules
+/// (types) or with constants (values).
+fn top_level_items(config: &Config) -> Vec<(&'static str, bool)> {
+    let mut items = vec![];
+    if config.key_table != KeyTable::None {
+        items.extend([("ALL_KEYS", false), ("is_known_key", false)]);
+    }
+    if config.key_lookup {
+        items.extend([("KeyInfo", true), ("lookup", false), (lookup::MODULE, true)]);
+    }
+    if config.typed_keys {
+        items.push(("Key", true));
+    }
+    items
+}
+
+/// Collects the elements generating items in the same module as `keys`, together with the names of the placeholders in their path.
+///
+/// Placeholders with children are transparent: the items of their children are generated into the enclosing module.
+/// The names of placeholders are validated on the way.
+fn module_items<'a>(
+    keys: &'a [KeyElement],
+    placeholders: &[&'a str],
+    config: &Config,
+    items: &mut Vec<(&'a KeyElement, Vec<&'a str>)>,
+) -> Result<(), KeygenError> {
+    for key in keys {
+        if key.placeholder().unwrap_or(&key.name).contains(['{', '}']) {
+            return Err(KeygenError::validation(
+                format!("the segment `{}` contains braces, but placeholders must span the whole segment (e.g. `{{id}}`)", key.name),
+                key.origin.clone(),
+            ));
+        }
+        let Some(name) = key.placeholder() else {
+            items.push((key, placeholders.to_vec()));
+            continue;
+        };
+
+        let param = key.parameter(name, config)?;
+        for other in placeholders {
+            if key.parameter(other, config)? == param {
+                return Err(KeygenError::validation(format!("the placeholder `{{{}}}` is used more than once in the key", name), key.origin.clone()));
+            }
+        }
+        let mut inner = placeholders.to_vec();
+        inner.push(name);
+        if key.children.is_empty() {
+            items.push((key, inner));
+        } else {
+            module_items(&key.children, &inner, config, items)?;
+        }
+    }
     Ok(())
 }
 
-fn compile_input(input: &str) -> Result<Vec<KeyElement>, String> {
+/// Returns the attributes that suppress warnings in the generated code.
+/// Naming warnings are only suppressed if the configured naming convention does not follow the rust conventions.
+fn control_macros(config: &Config) -> String {
+    if config.enable_warnings {
+        return "".to_string();
+    }
+
+    let mut control_macros = "#[allow(dead_code)]\n".to_string();
+    if config.constant_case != Case::ScreamingSnakeCase {
+        control_macros += "#[allow(non_upper_case_globals)]\n";
+    }
+    if config.module_case != Case::SnakeCase {
+        control_macros += "#[allow(non_snake_case)]\n";
+    }
+    control_macros
+}
+
+/// Compiles an input in the key format.
+///
+/// `path` is the file the input was read from, `@include` directives are resolved relative to it.
+/// All included files are added to `included`.
+fn compile_keys(input: &str, path: Option<&Path>, config: &Config, included: &mut Vec<PathBuf>) -> Result<Vec<KeyElement>, KeygenError> {
+    let chain = path.and_then(|p| p.canonicalize().ok()).into_iter().collect::<Vec<PathBuf>>();
+    compile_included(input, path, config, &chain, included)
+}
+
+/// Compiles an input in the key format, which was reached through the (canonical) files in `chain`.
+fn compile_included(
+    input: &str,
+    path: Option<&Path>,
+    config: &Config,
+    chain: &[PathBuf],
+    included: &mut Vec<PathBuf>,
+) -> Result<Vec<KeyElement>, KeygenError> {
     let lines = input.lines();
 
-    let mut root = KeyElement {
-        name: "".to_string(),
-        children: vec![],
-    };
+    let mut root = KeyElement::new("");
+    let mut pending_doc: Vec<&str> = vec![];
     let mut previous_line = "".to_string();
+    let mut previous_is_include = false;
     let mut current_indentation = 0;
     let mut current_parent = "".to_string();
     let mut indentations = vec![];
 
-    for ln in lines {
-        let indent = count_leading_whitespaces(ln);
-        let key = ln.trim_start().to_string();
+    for (line_index, ln) in lines.enumerate() {
+        let (content, doc) = split_comment(ln);
+        if content.trim().is_empty() {
+            pending_doc.extend(doc);
+            continue;
+        }
+        let indent = count_leading_whitespaces(content);
+        let column = content.chars().take_while(|c| c.is_whitespace()).count() + 1;
+        let mut origin = SourceLocation::new(line_index + 1, column, ln);
+        origin.path = path.map(Path::to_path_buf);
+        let location_at = |offset: usize| SourceLocation {
+            column: content[..offset].chars().count() + 1,
+            ..origin.clone()
+        };
+        let (key, attributes, value) = split_line(content)
+            .map_err(|(message, offset)| KeygenError::parse(message, location_at(offset)))?;
+        let key = key.to_string();
 
         if indent > current_indentation {
+            if previous_is_include {
+                return Err(KeygenError::parse("keys can not be nested below `@include`", origin));
+            }
             indentations.push((current_indentation, current_parent.to_string()));
             current_indentation = indent;
             if current_parent.is_empty() {
@@ -132,23 +847,59 @@ fn compile_input(input: &str) -> Result<Vec<KeyElement>, String> {
                 current_parent = current_parent + "." + &previous_line;
             }
         } else if indent < current_indentation {
-            let mut restore = indentations.pop().unwrap();
-            while restore.0 != indent {
-                restore = indentations.pop().unwrap();
-
-                if restore.0 < indent {
-                    return Err("Illegal indentation in line \"".to_string() + ln + "\"!");
+            loop {
+                match indentations.pop() {
+                    Some((level, parent)) if level == indent => {
+                        current_indentation = level;
+                        current_parent = parent;
+                        break;
+                    }
+                    Some((level, _)) if level > indent => continue,
+                    _ => {
+                        let column = ln.chars().take_while(|c| c.is_whitespace()).count() + 1;
+                        return Err(KeygenError::parse(
+                            "illegal indentation: does not match any enclosing level",
+                            SourceLocation::new(line_index + 1, column, ln),
+                        ));
+                    }
                 }
             }
+        }
 
-            current_indentation = restore.0;
-            current_parent = restore.1;
+        pending_doc.extend(doc);
+        let include = include_target(&key);
+        previous_is_include = include.is_some();
+        if let Some(target) = include {
+            if pending_doc.is_empty().not() {
+                return Err(KeygenError::parse("doc comments can not be attached to `@include`", origin));
+            }
+            if value.is_some() || attributes.is_empty().not() {
+                return Err(KeygenError::parse("`@include` can not have attributes or a value", origin));
+            }
+            let keys = compile_include(target, path, &origin, config, chain, included)?;
+            let mount = if current_parent.is_empty() {
+                &mut root
+            } else {
+                root.create_key(&current_parent, &origin)
+            };
+            mount.merge(keys, &current_parent)?;
+            continue;
         }
 
-        if current_parent.is_empty() {
-            root.create_key(&key);
-        } else {
-            root.create_key(&(current_parent.to_string() + "." + &key));
+        let element = root.create_key_below(&current_parent, &key, &origin);
+        if let Some(value) = value {
+            element.assign("values", |e| &mut e.value, value, &origin)?;
+        }
+        for (name, value, offset) in attributes {
+            match name {
+                "separator" => element.assign("separators", |e| &mut e.separator, value, &origin)?,
+                "type" => element.assign("types", |e| &mut e.value_type, value, &origin)?,
+                _ => return Err(KeygenError::parse(format!("unknown attribute `{}`", name), location_at(offset))),
+            }
+        }
+        if pending_doc.is_empty().not() {
+            element.assign("doc comments", |e| &mut e.doc, pending_doc.join("\n"), &origin)?;
+            pending_doc.clear();
         }
 
         previous_line = key;
@@ -157,8 +908,179 @@ fn compile_input(input: &str) -> Result<Vec<KeyElement>, String> {
     Ok(root.children)
 }
 
+/// Returns the path of an `@include` directive, or `None` if the line is a regular key.
+fn include_target(line: &str) -> Option<&str> {
+    line.strip_prefix("@include")
+        .filter(|target| target.is_empty() || target.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+/// Reads and compiles the file referenced by an `@include` directive at `origin`.
+/// The format of the file is detected from its extension, see [`InputFormat::from_path`].
+/// The returned element contains the keys of the file as children.
+fn compile_include(
+    target: &str,
+    path: Option<&Path>,
+    origin: &SourceLocation,
+    config: &Config,
+    chain: &[PathBuf],
+    included: &mut Vec<PathBuf>,
+) -> Result<KeyElement, KeygenError> {
+    if target.is_empty() {
+        return Err(KeygenError::parse("missing path after `@include`", origin.clone()));
+    }
+    let include_path = match path.and_then(Path::parent) {
+        Some(dir) => dir.join(target),
+        None => PathBuf::from(target),
+    };
+
+    let input = std::fs::read_to_string(&include_path)
+        .and_then(|input| include_path.canonicalize().map(|canonical| (input, canonical)));
+    let (input, canonical) = match input {
+        Ok(input) => input,
+        Err(e) => {
+            let message = format!("can not read included file `{}`: {}", include_path.display(), e);
+            return Err(KeygenError::parse(message, origin.clone()));
+        }
+    };
+    if chain.contains(&canonical) {
+        return Err(KeygenError::parse(format!("cyclic include of `{}`", include_path.display()), origin.clone()));
+    }
+    if included.contains(&include_path).not() {
+        included.push(include_path.clone());
+    }
+
+    let chain = chain.iter().cloned().chain([canonical]).collect::<Vec<PathBuf>>();
+    let compiled = match InputFormat::from_path(&include_path) {
+        InputFormat::Keys => compile_included(&input, Some(&include_path), config, &chain, included),
+        format => format.compile(&input, Some(&include_path), config, included),
+    };
+    let mut keys = KeyElement::new("");
+    keys.children = compiled
+        .map_err(|e| e.with_path(&include_path).with_note(format!("`{}` is included here", include_path.display()), origin.clone()))?;
+    keys.set_path(&include_path);
+    Ok(keys)
+}
+
+/// Splits the line into its content and the text of a `##` doc comment.
+/// Regular `#` comments are removed, including the whitespace preceding them.
+/// A `#` inside a quoted value does not start a comment.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut in_string = false;
+    let mut escaped = false;
+    let comment_start = line.char_indices().find(|&(_, c)| {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = in_string.not(),
+            '#' if in_string.not() => return true,
+            _ => {}
+        }
+        false
+    });
+
+    match comment_start {
+        Some((index, _)) => {
+            let doc = line[index + 1..].strip_prefix('#').map(str::trim);
+            (line[..index].trim_end(), doc)
+        }
+        None => (line.trim_end(), None),
+    }
+}
+
+/// Splits the content of a line into the key, its attributes (`[name="value", ...]`) and its explicit value (`= "value"`).
+///
+/// Values are quoted strings, which may contain the escape sequences `\"`, `\\`, `\n` and `\t`.
+/// Attributes are returned with the byte offset of their name. If the line is malformed, the message and the byte offset
+/// of the problem are returned.
+fn split_line(content: &str) -> Result<ParsedLine<'_>, (&'static str, usize)> {
+    let skip_whitespace = |position: usize| content.len() - content[position..].trim_start().len();
+
+    let key_end = content.find(['[', '=']).unwrap_or(content.len());
+    let key = content[..key_end].trim();
+    if key.is_empty() {
+        return Err(("missing key", key_end));
+    }
+
+    let mut position = key_end;
+    let mut attributes = vec![];
+    if content[position..].starts_with('[') {
+        position += 1;
+        loop {
+            position = skip_whitespace(position);
+            let name_end = content[position..]
+                .find(|c: char| c.is_alphanumeric().not() && c != '_' && c != '-')
+                .map_or(content.len(), |i| position + i);
+            if name_end == position {
+                return Err(("expected an attribute name", position));
+            }
+            let name_position = position;
+            position = skip_whitespace(name_end);
+            if content[position..].starts_with('=').not() {
+                return Err(("expected `=` after the attribute name", position));
+            }
+            let (value, end) = parse_string(content, skip_whitespace(position + 1))?;
+            attributes.push((&content[name_position..name_end], value, name_position));
+
+            position = skip_whitespace(end);
+            match content[position..].chars().next() {
+                Some(',') => position += 1,
+                Some(']') => {
+                    position += 1;
+                    break;
+                }
+                _ => return Err(("expected `,` or `]` after the attribute", position)),
+            }
+        }
+    }
+
+    position = skip_whitespace(position);
+    let mut value = None;
+    if content[position..].starts_with('=') {
+        let (parsed, end) = parse_string(content, skip_whitespace(position + 1))?;
+        value = Some(parsed);
+        position = skip_whitespace(end);
+    }
+    if position < content.len() {
+        return Err(("unexpected characters at the end of the line", position));
+    }
+    Ok((key, attributes, value))
+}
+
+/// Key, attributes (with the byte offset of their name) and explicit value of a line.
+type ParsedLine<'a> = (&'a str, Vec<(&'a str, String, usize)>, Option<String>);
+
+/// Parses the quoted string starting at the byte offset `start` and returns its content and the offset following it.
+fn parse_string(content: &str, start: usize) -> Result<(String, usize), (&'static str, usize)> {
+    let Some(quoted) = content[start..].strip_prefix('"') else {
+        return Err(("expected a quoted string", start));
+    };
+    let mut parsed = "".to_string();
+    let mut chars = quoted.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Ok((parsed, start + index + 2)),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => parsed.push('\n'),
+                Some((_, 't')) => parsed.push('\t'),
+                Some((_, c @ ('"' | '\\'))) => parsed.push(c),
+                _ => return Err(("unknown escape sequence", start + index + 1)),
+            },
+            c => parsed.push(c),
+        }
+    }
+    Err(("unterminated string", start))
+}
+
+fn sort_keys(keys: &mut [KeyElement]) {
+    keys.sort_by(|a, b| a.name.cmp(&b.name));
+    for key in keys {
+        sort_keys(&mut key.children);
+    }
+}
+
 fn count_leading_whitespaces(line: &str) -> usize {
-    let replaced = line.replace("\t", "    ");
+    let replaced = line.replace('\t', "    ");
     let unindented = replaced.trim_start();
     replaced.len() - unindented.len()
 }
@@ -185,44 +1107,415 @@ mod tests {
         assert_eq!(expecded_structure(), compile_input(input).unwrap());
     }
 
-    fn expecded_structure() -> Vec<KeyElement> {
-        vec![KeyElement {
-            name: "hierarchical".to_string(),
-            children: vec![
-                KeyElement {
-                    name: "keys".to_string(),
-                    children: vec![
-                        KeyElement {
-                            name: "with".to_string(),
-                            children: vec![
-                                KeyElement {
-                                    name: "five".to_string(),
-                                    children: vec![
-                                        KeyElement {
-                                            name: "layers".to_string(),
-                                            children: vec![],
-                                        }
-                                    ],
-                                },
-                                KeyElement {
-                                    name: "six".to_string(),
-                                    children: vec![
-                                        KeyElement {
-                                            name: "hierarchical".to_string(),
-                                            children: vec![
-                                                KeyElement {
-                                                    name: "layers".to_string(),
-                                                    children: vec![],
-                                                }
-                                            ],
-                                        }
-                                    ],
-                                },
-                            ],
-                        }
-                    ],
+    #[test]
+    fn commented_input_compiles() {
+        let input = include_str!("test/commented.keys");
+        assert_eq!(expecded_structure(), compile_input(input).unwrap());
+    }
+
+    #[test]
+    fn illegal_indentation_is_reported() {
+        let input = "root\n    child\n  sibling\n";
+        match compile_input(input) {
+            Err(KeygenError::Parse { location, .. }) => {
+                assert_eq!(SourceLocation::new(3, 3, "  sibling"), location);
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_input_file_is_an_io_error() {
+        let result = Config::new().input("src/test/does-not-exist.keys").generate();
+        assert!(matches!(result, Err(KeygenError::Io { .. })));
+    }
+
+    #[test]
+    fn inputs_are_merged() {
+        let mut root = KeyElement::new("");
+        for input in ["a.b\nc", "a.d\nc.e"] {
+            let mut compiled = KeyElement::new("");
+            compiled.children = compile_input(input).unwrap();
+            root.merge(compiled, "").unwrap();
+        }
+        let expected = vec![
+            key("a", vec![key("b", vec![]), key("d", vec![])]),
+            key("c", vec![key("e", vec![])]),
+        ];
+        assert_eq!(expected, root.children);
+    }
+
+    #[test]
+    fn contradicting_inputs_are_reported_with_both_locations() {
+        let mut root = KeyElement::new("");
+        for (path, input) in [("auth.keys", "server\n  ## Port of the server.\n  port"), ("billing.keys", "## Billing port.\nserver.port")] {
+            let mut compiled = KeyElement::new("");
+            compiled.children = compile_input(input).unwrap();
+            compiled.set_path(Path::new(path));
+            match root.merge(compiled, "") {
+                Ok(()) => continue,
+                Err(KeygenError::Validation { message, location, notes }) => {
+                    assert_eq!("the key `server.port` is documented differently in multiple inputs", message);
+                    let location = location.unwrap();
+                    assert_eq!((Some(Path::new("billing.keys")), 2, 8), (location.path.as_deref(), location.line, location.column));
+                    let note = &notes[0].1;
+                    assert_eq!((Some(Path::new("auth.keys")), 3, 3), (note.path.as_deref(), note.line, note.column));
+                    return;
+                }
+                Err(other) => panic!("expected validation error, got {:?}", other),
+            }
+        }
+        panic!("expected validation error");
+    }
+
+    #[test]
+    fn included_files_are_mounted_below_parent() {
+        let path = Path::new("src/test/include/main.keys");
+        let mut included = vec![];
+        let compiled = compile_keys(include_str!("test/include/main.keys"), Some(path), &Config::new(), &mut included).unwrap();
+
+        let mut metrics = key("metrics", vec![key("requests", vec![]), key("errors", vec![])]);
+        metrics.doc = Some("Metrics of the service.".to_string());
+        assert_eq!(vec![key("service", vec![key("name", vec![]), metrics])], compiled);
+        assert_eq!(vec![PathBuf::from("src/test/include/common/metrics.keys")], included);
+
+        let origin = compiled[0].children[1].origin.as_ref().unwrap();
+        assert_eq!((Some(Path::new("src/test/include/common/metrics.keys")), 2), (origin.path.as_deref(), origin.line));
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn included_files_are_compiled_in_their_format() {
+        let path = Path::new("src/test/include/yaml.keys");
+        let mut included = vec![];
+        let compiled = compile_keys(include_str!("test/include/yaml.keys"), Some(path), &Config::new(), &mut included).unwrap();
+
+        let server = key("server", vec![key("host", vec![]), key("port", vec![])]);
+        assert_eq!(vec![key("service", vec![server])], compiled);
+        assert_eq!(vec![PathBuf::from("src/test/include/settings.yaml")], included);
+    }
+
+    #[test]
+    fn include_cycles_are_reported_with_include_chain() {
+        let path = Path::new("src/test/include/cycle_a.keys");
+        match compile_keys(include_str!("test/include/cycle_a.keys"), Some(path), &Config::new(), &mut vec![]) {
+            Err(KeygenError::Parse { message, location, notes }) => {
+                assert_eq!("cyclic include of `src/test/include/cycle_a.keys`", message);
+                assert_eq!((Some(Path::new("src/test/include/cycle_b.keys")), 2), (location.path.as_deref(), location.line));
+                assert_eq!(1, notes.len());
+                assert_eq!("`src/test/include/cycle_b.keys` is included here", notes[0].0);
+                assert_eq!((Some(path), 2), (notes[0].1.path.as_deref(), notes[0].1.line));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn explicit_values_override_key_strings() {
+        let input = "login = \"LOGIN\"\n  title = \"LOGIN_SCREEN_TITLE\"  # legacy\n  button\nlogin.hint = \"#hint \\\"quoted\\\"\"";
+        let config = Config::new().enable_warnings(true);
+        assert_eq!(
+            "pub mod login {pub const _BASE : &str = \"LOGIN\";\
+            pub const title: &str = \"LOGIN_SCREEN_TITLE\";\
+            pub const button: &str = \"login.button\";\
+            pub const hint: &str = \"#hint \\\"quoted\\\"\"; }",
+            generate_from_str(input, &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_values_are_reported() {
+        for (input, column) in [("a = b", 5), ("a = \"b", 5), ("a = \"b\" c", 9), ("a = \"\\x\"", 6), (" = \"a\"", 2)] {
+            match compile_input(input) {
+                Err(KeygenError::Parse { location, .. }) => assert_eq!(column, location.column, "{}", input),
+                other => panic!("expected parse error for {}, got {:?}", input, other),
+            }
+        }
+        assert!(matches!(compile_input("a = \"x\"\na = \"y\""), Err(KeygenError::Validation { .. })));
+    }
+
+    #[test]
+    fn separators_are_overridden_for_subtrees() {
+        let input = "topics\n  cache [separator=\":\"]\n    user\n      name\n  orders.created";
+        let config = Config::new().separator("/").enable_warnings(true);
+        assert_eq!(
+            "pub mod topics {pub const _BASE : &str = \"topics\";\
+            pub mod cache {pub const _BASE : &str = \"topics/cache\";\
+            pub mod user {pub const _BASE : &str = \"topics/cache:user\";pub const name: &str = \"topics/cache:user:name\"; } }\
+            pub mod orders {pub const _BASE : &str = \"topics/orders\";pub const created: &str = \"topics/orders/created\"; } }",
+            generate_from_str(input, &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_attributes_are_reported() {
+        for (input, column) in [("a [sep=\":\"]", 4), ("a [separator]", 13), ("a [separator=\":\"", 17), ("[separator=\":\"]", 1)] {
+            match compile_input(input) {
+                Err(KeygenError::Parse { location, .. }) => assert_eq!(column, location.column, "{}", input),
+                other => panic!("expected parse error for {}, got {:?}", input, other),
+            }
+        }
+        let compiled = compile_input("a [separator=\":\"] = \"A\"").unwrap();
+        assert_eq!((Some(":"), Some("A")), (compiled[0].separator.as_deref(), compiled[0].value.as_deref()));
+    }
+
+    #[test]
+    fn placeholders_generate_functions() {
+        let config = Config::new().enable_warnings(true);
+        assert_eq!(
+            "pub mod user {pub const _BASE : &str = \"user\";\
+            pub fn profile(id: impl ::std::fmt::Display) -> ::std::string::String { ::std::format!(\"user.{}.profile\", id) } }",
+            generate_from_str("user.{id}.profile", &config).unwrap()
+        );
+        assert_eq!(
+            "pub mod queue {pub const _BASE : &str = \"queue\";\
+            pub fn name(tenant: impl ::std::fmt::Display, name: impl ::std::fmt::Display) -> ::std::string::String { ::std::format!(\"queue:{}:{}\", tenant, name) } }",
+            generate_from_str("queue.{tenant}.{name}", &config.clone().separator(":")).unwrap()
+        );
+
+        // A parameter named like a constant of the module would refer to the constant.
+        let code = generate_from_str("user.{id}.{id_}.profile\nuser.id", &config).unwrap();
+        assert!(code.contains("pub fn profile(id__: impl ::std::fmt::Display, id_: impl ::std::fmt::Display)"), "{}", code);
+        assert!(code.contains("::std::format!(\"user.{}.{}.profile\", id__, id_)"), "{}", code);
+    }
+
+    #[test]
+    fn invalid_placeholders_are_reported() {
+        let config = Config::new();
+        for input in ["user.{id}.group.{id}", "user.{}.profile", "user.{user-id}.profile", "user.{id}.profile = \"PROFILE\""] {
+            assert!(generate_from_str(input, &config).is_err(), "{}", input);
+        }
+        assert!(generate_from_str("user.{id}.profile\nuser.profile", &config).is_err());
+
+        for (input, segment) in [("user.{id}x.profile", "{id}x"), ("a.{b.c}.d", "{b"), ("a\n  {{b}}", "{{b}}"), ("a\n  b}", "b}")] {
+            match generate_from_str(input, &config) {
+                Err(KeygenError::Validation { message, location: Some(location), .. }) => {
+                    assert!(message.starts_with(&format!("the segment `{}` contains braces", segment)), "{}", message);
+                    assert_eq!(input.lines().count(), location.line, "{}", input);
+                }
+                other => panic!("expected validation error for {}, got {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn key_enums_are_generated() {
+        let config = Config::new().enable_warnings(true).key_enums(true);
+        let code = generate_from_str("server\n  http-port\n  tls.cert\n  user.{id}", &config).unwrap();
+        assert!(code.contains("pub enum Key { HttpPort, Tls(tls::Key) }"), "{}", code);
+        assert!(code.contains("pub const ALL: &'static [Key] = &[Key::HttpPort, Key::Tls(tls::Key::Cert)];"), "{}", code);
+        assert!(code.contains("match *self { Key::HttpPort => http_port, Key::Tls(key) => key.as_str(), }"), "{}", code);
+        assert!(code.contains("pub enum Key { Cert }"), "{}", code);
+        assert_eq!(2, code.matches("pub enum Key").count());
+
+        let code = generate_from_str("server.port", &config.clone().root_module("keys")).unwrap();
+        assert!(code.contains("pub const ALL: &'static [Key] = &[Key::Server(server::Key::Port)];"), "{}", code);
+
+        // Bindings named like a constant of the module would refer to the constant.
+        let code = generate_from_str("server\n  key\n  s\n  tls.cert", &config).unwrap();
+        assert!(code.contains("Key::Tls(key_) => key_.as_str(),"), "{}", code);
+        assert!(code.contains("fn from_str(s_: &str)"), "{}", code);
+    }
+
+    #[test]
+    fn key_enum_collisions_are_reported() {
+        let config = Config::new().key_enums(true);
+        assert!(generate_from_str("server\n  http-port\n  HttpPort", &config).is_err());
+        assert!(generate_from_str("server\n  Key.port", &config).is_err());
+        assert!(generate_from_str("server\n  port\n  {id}.Key.port", &config).is_err());
+        assert!(generate_from_str("Key.{id}.tls\nother.a", &config.clone().root_module("keys")).is_err());
+        assert!(generate_from_str("Key.{id}.tls\nother.a", &config).is_ok());
+        assert!(generate_from_str("server\n  http-port\n  HttpPort", &Config::new()).is_ok());
+    }
+
+    #[test]
+    fn key_table_is_generated() {
+        let input = "server\n  port\n  host = \"HOST\"\n  user.{id}\nclient.timeout";
+        let config = Config::new().enable_warnings(true).key_table(KeyTable::Leaves);
+        let code = generate_from_str(input, &config).unwrap();
+        assert!(code.ends_with("\npub const ALL_KEYS: &[&str] = &[\"HOST\", \"client.timeout\", \"server.port\"];\
+            \npub fn is_known_key(key: &str) -> bool { ALL_KEYS.binary_search(&key).is_ok() }"), "{}", code);
+
+        let code = generate_from_str(input, &config.clone().key_table(KeyTable::LeavesAndBases)).unwrap();
+        assert!(code.contains("&[\"HOST\", \"client\", \"client.timeout\", \"server\", \"server.port\", \"server.user\"]"), "{}", code);
+
+        let code = generate_from_str("key", &config).unwrap();
+        assert!(code.ends_with("pub fn is_known_key(key_: &str) -> bool { ALL_KEYS.binary_search(&key_).is_ok() }"), "{}", code);
+        assert!(generate_from_str("ALL_KEYS", &Config::new().key_table(KeyTable::Leaves)).is_err());
+    }
+
+    #[test]
+    fn key_lookup_is_generated() {
+        let config = Config::new().enable_warnings(true).key_lookup(true);
+        let code = generate_from_str("## Port to listen on.\nserver.port [type=\"u16\"]", &config).unwrap();
+        assert!(code.contains("pub fn lookup(key: &str) -> ::std::option::Option<&'static KeyInfo> { __key_lookup::lookup(key) }"), "{}", code);
+        assert!(code.contains("super::KeyInfo { key: \"server.port\", segments: &[\"server\", \"port\"], \
+            doc: Some(\"Port to listen on.\"), value_type: Some(\"u16\"), file: None, line: Some(2) }"), "{}", code);
+
+        match generate_from_str("a = \"X\"\nb = \"X\"", &config) {
+            Err(KeygenError::Validation { message, .. }) => assert_eq!("the keys `a` and `b` both have the key string `X`", message),
+            other => panic!("expected validation error, got {:?}", other),
+        }
+        assert!(generate_from_str("lookup", &config).is_err());
+        assert!(generate_from_str("KeyInfo.a", &config).is_err());
+
+        let code = generate_from_str("key\nOption.a", &config).unwrap();
+        assert!(code.contains("pub fn lookup(key_: &str)"), "{}", code);
+    }
+
+    #[test]
+    fn typed_keys_are_generated() {
+        let config = Config::new().enable_warnings(true).typed_keys(true);
+        let code = generate_from_str("server\n  tls.cert\nport", &config).unwrap();
+        assert!(code.starts_with("#[repr(transparent)] #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)] pub struct Key { key: &'static str }"), "{}", code);
+        assert!(code.contains("pub mod server {pub const _BASE : super::Key = super::Key::new(\"server\");\
+            pub mod tls {pub const _BASE : super::super::Key = super::super::Key::new(\"server.tls\");\
+            pub const cert: super::super::Key = super::super::Key::new(\"server.tls.cert\"); } }"), "{}", code);
+        assert!(code.ends_with("\npub const port: Key = Key::new(\"port\");"), "{}", code);
+
+        assert!(generate_from_str("Key.a", &config).is_err());
+
+        // A tuple struct would collide with the constant `Key`, the parameters `key` and `f` with the constants of these names.
+        let code = generate_from_str("Key\nkey\nf", &config).unwrap();
+        assert!(code.contains("pub(crate) const fn new(key_: &'static str) -> Key { Key { key: key_ } }"), "{}", code);
+        assert!(code.contains("fn fmt(&self, f_: &mut ::std::fmt::Formatter<'_>)"), "{}", code);
+        assert!(generate_from_str("a.b", &config.clone().key_enums(true)).is_ok());
+        assert!(generate_from_str("a.b", &config.key_enums(true).root_module("keys")).is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_functions_keep_their_signatures() {
+        let _: fn(&PathBuf) -> Result<(), KeygenError> = generate;
+        let _: fn(&PathBuf, Option<&PathBuf>, bool, &str) -> Result<(), KeygenError> = generate_with_config;
+    }
+
+    #[test]
+    fn code_is_generated_in_memory() {
+        let config = Config::new().separator("/").enable_warnings(true);
+        assert_eq!(
+            "pub mod a {pub const _BASE : &str = \"a\";pub const b: &str = \"a/b\"; }",
+            generate_from_str("a.b", &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn keys_are_sorted_and_wrapped_in_root_module() {
+        let config = Config::new()
+            .enable_warnings(true)
+            .sort_keys(true)
+            .root_module("keys")
+            .visibility(Visibility::Crate);
+        assert_eq!(
+            "pub(crate) mod keys {\npub(crate) const a: &str = \"a\";\npub(crate) const b: &str = \"b\";\n}",
+            generate_from_str("b\na", &config).unwrap()
+        );
+
+        assert!(generate_from_str("a", &config.clone().root_module("type")).unwrap().starts_with("pub(crate) mod r#type {"));
+        for name in ["my-keys", "self", ""] {
+            match generate_from_str("a", &config.clone().root_module(name)) {
+                Err(KeygenError::Validation { message, .. }) => {
+                    assert_eq!(format!("the root module `{}` is not a valid rust identifier", name), message);
                 }
-            ],
-        }]
+                other => panic!("expected validation error for {}, got {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn doc_comments_are_generated() {
+        let input = include_str!("test/documented.keys");
+        let config = Config::new().enable_warnings(true);
+        assert_eq!(
+            "/// Settings of the server.\n/// Read on startup.\n\
+            pub mod server {pub const _BASE : &str = \"server\";\
+            \n/// Port to listen on.\npub const port: &str = \"server.port\";\
+            pub const host: &str = \"server.host\"; }",
+            generate_from_str(input, &config).unwrap()
+        );
+
+        assert!(generate_from_str("## d\nc\n## d\nc", &config).is_ok());
+        match generate_from_str("## d1\nc\n## d2\nc", &config) {
+            Err(KeygenError::Validation { message, location, .. }) => {
+                assert_eq!("the key `c` has different doc comments", message);
+                assert_eq!(4, location.unwrap().line);
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_identifiers_are_reported_with_location() {
+        let config = Config::new().identifier_policy(IdentifierPolicy::Error);
+        match generate_from_str("server\n  http-port", &config) {
+            Err(KeygenError::Codegen { location, .. }) => {
+                assert_eq!(Some(SourceLocation::new(2, 3, "  http-port")), location);
+            }
+            other => panic!("expected codegen error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keywords_and_special_characters_are_escaped() {
+        let config = Config::new().enable_warnings(true);
+        assert_eq!(
+            "pub mod r#type {pub const _BASE : &str = \"type\";pub const _2fa: &str = \"type.2fa\";pub const say_hi_: &str = \"type.say\\\"hi\\\"\"; }",
+            generate_from_str("type\n  2fa\n  say\"hi\"", &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn identifiers_follow_naming_policy() {
+        let config = Config::new()
+            .constant_case(Case::ScreamingSnakeCase)
+            .module_case(Case::SnakeCase);
+        assert_eq!(
+            "#[allow(dead_code)]\npub mod http_server {pub const _BASE : &str = \"httpServer\";pub const MAX_CONNECTIONS: &str = \"httpServer.max-connections\"; }",
+            generate_from_str("httpServer.max-connections", &config).unwrap()
+        );
+    }
+
+    #[test]
+    fn identifier_collisions_are_reported() {
+        let config = Config::new();
+        match generate_from_str("server\n  foo-bar\n  foo_bar", &config) {
+            Err(KeygenError::Validation { location, notes, .. }) => {
+                assert_eq!(Some(SourceLocation::new(3, 3, "  foo_bar")), location);
+                assert_eq!(vec![SourceLocation::new(2, 3, "  foo-bar")], notes.into_iter().map(|(_, l)| l).collect::<Vec<_>>());
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+        assert!(generate_from_str("server._BASE", &config).is_err());
+        assert!(generate_from_str("server.foo\nserver.foo.bar", &config).is_ok());
+    }
+
+    fn compile_input(input: &str) -> Result<Vec<KeyElement>, KeygenError> {
+        compile_keys(input, None, &Config::new(), &mut vec![])
+    }
+
+    fn key(name: &str, children: Vec<KeyElement>) -> KeyElement {
+        KeyElement {
+            children,
+            ..KeyElement::new(name)
+        }
+    }
+
+    pub(crate) fn expecded_structure() -> Vec<KeyElement> {
+        vec![
+            key("hierarchical", vec![
+                key("keys", vec![
+                    key("with", vec![
+                        key("five", vec![
+                            key("layers", vec![]),
+                        ]),
+                        key("six", vec![
+                            key("hierarchical", vec![
+                                key("layers", vec![]),
+                            ]),
+                        ]),
+                    ]),
+                ]),
+            ]),
+        ]
     }
 }
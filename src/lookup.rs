@@ -0,0 +1,157 @@
+//! Generation of the function `lookup`, which maps key strings to their metadata.
+//!
+//! The keys are stored in a minimal perfect hash table built with the "hash and displace" scheme:
+//! every key is assigned to a bucket by its hash with seed `0`. For each bucket a seed (its displacement) is searched,
+//! which places all keys of the bucket into free slots of the table. At runtime the bucket of a key yields the seed
+//! of the key and thereby its slot, so no probing is necessary.
+
+use std::ops::Not;
+
+use crate::{ident, unused_name, Config, KeygenError, TableEntry};
+
+/// Name of the private module containing the table.
+pub(crate) const MODULE: &str = "__key_lookup";
+
+/// Upper bound for the search of a displacement, so the generation terminates even for pathological inputs.
+const MAX_SEED: u32 = 1_000_000;
+
+/// Runtime version of [`hash`], which has to compute exactly the same hashes.
+const HASH_FUNCTION: &str = "fn hash(key: &str, seed: u64) -> u64 { let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed; \
+    for byte in key.bytes() { hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3); } \
+    hash ^= hash >> 33; hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd); hash ^ (hash >> 33) }";
+
+/// Seeded FNV-1a, followed by a finalizer to spread the bits of short keys.
+fn hash(key: &str, seed: u64) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in key.bytes() {
+        hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^ (hash >> 33)
+}
+
+/// Generates the struct `KeyInfo`, the function `lookup` and the private module containing the table.
+/// `constants` are the identifiers of the constants at the top level.
+pub(crate) fn generate(table: &[TableEntry], constants: &[String], config: &Config) -> Result<Vec<String>, KeygenError> {
+    for (index, entry) in table.iter().enumerate() {
+        if let Some(other) = table[..index].iter().find(|o| o.key == entry.key) {
+            return Err(KeygenError::Validation {
+                message: format!("the keys `{}` and `{}` both have the key string `{}`", other.segments.join("."), entry.segments.join("."), entry.key),
+                location: entry.element.origin.clone(),
+                notes: other.element.origin.iter().map(|o| (format!("`{}` is defined here", other.segments.join(".")), o.clone())).collect(),
+            });
+        }
+    }
+    let (displacements, slots) = build(table)?;
+
+    let vis = config.visibility.keyword();
+    let key_info = format!(
+        "#[derive(Copy, Clone, Debug, PartialEq, Eq)] {vis} struct KeyInfo {{ {vis} key: &'static str, \
+        {vis} segments: &'static [&'static str], {vis} doc: ::std::option::Option<&'static str>, \
+        {vis} value_type: ::std::option::Option<&'static str>, {vis} file: ::std::option::Option<&'static str>, \
+        {vis} line: ::std::option::Option<u32> }}",
+        vis = vis,
+    );
+    let lookup = format!(
+        "{} fn lookup({key}: &str) -> ::std::option::Option<&'static KeyInfo> {{ {}::lookup({key}) }}",
+        vis,
+        MODULE,
+        key = unused_name("key", constants),
+    );
+
+    let module = if table.is_empty() {
+        format!("mod {} {{ pub(super) fn lookup(_key: &str) -> Option<&'static super::KeyInfo> {{ None }} }}", MODULE)
+    } else {
+        let displacements = displacements.iter().map(u32::to_string).collect::<Vec<String>>();
+        let infos = slots.iter().map(|&index| key_info_literal(&table[index])).collect::<Vec<String>>();
+        format!(
+            "mod {} {{ const DISPLACEMENTS: [u32; {}] = [{}]; static KEYS: [super::KeyInfo; {}] = [{}]; {} \
+            pub(super) fn lookup(key: &str) -> Option<&'static super::KeyInfo> {{ \
+            let bucket = (hash(key, 0) % DISPLACEMENTS.len() as u64) as usize; \
+            let slot = (hash(key, u64::from(DISPLACEMENTS[bucket])) % KEYS.len() as u64) as usize; \
+            Some(&KEYS[slot]).filter(|info| info.key == key) }} }}",
+            MODULE,
+            displacements.len(),
+            displacements.join(", "),
+            infos.len(),
+            infos.join(", "),
+            HASH_FUNCTION,
+        )
+    };
+    Ok(vec![key_info, lookup, module])
+}
+
+/// Builds the perfect hash table. Returns the displacement of every bucket and the index of the entry in every slot.
+fn build(table: &[TableEntry]) -> Result<(Vec<u32>, Vec<usize>), KeygenError> {
+    let size = table.len() as u64;
+    let mut buckets = vec![vec![]; table.len()];
+    for (index, entry) in table.iter().enumerate() {
+        buckets[(hash(&entry.key, 0) % size) as usize].push(index);
+    }
+
+    // Large buckets are placed first, while most slots are still free.
+    let mut order = (0..buckets.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|&b| std::cmp::Reverse(buckets[b].len()));
+
+    let mut slots: Vec<Option<usize>> = vec![None; table.len()];
+    let mut displacements = vec![0; buckets.len()];
+    for bucket in order.into_iter().filter(|&b| buckets[b].is_empty().not()) {
+        let placed = (1..=MAX_SEED).find_map(|seed| {
+            let positions = buckets[bucket].iter()
+                .map(|&index| (hash(&table[index].key, u64::from(seed)) % size) as usize)
+                .collect::<Vec<usize>>();
+            let free = positions.iter().enumerate()
+                .all(|(i, &p)| slots[p].is_none() && positions[..i].contains(&p).not());
+            free.then_some((seed, positions))
+        });
+        let Some((seed, positions)) = placed else {
+            return Err(KeygenError::Codegen { message: "could not build the hash table for `lookup`".to_string(), location: None });
+        };
+
+        displacements[bucket] = seed;
+        for (&index, position) in buckets[bucket].iter().zip(positions) {
+            slots[position] = Some(index);
+        }
+    }
+    Ok((displacements, slots.into_iter().flatten().collect()))
+}
+
+fn key_info_literal(entry: &TableEntry) -> String {
+    let optional = |value: Option<&str>| value.map_or("None".to_string(), |v| format!("Some({})", ident::string_literal(v)));
+    let origin = entry.element.origin.as_ref();
+    let file = origin.and_then(|o| o.path.as_ref()).map(|p| p.display().to_string());
+    format!(
+        "super::KeyInfo {{ key: {}, segments: &[{}], doc: {}, value_type: {}, file: {}, line: {} }}",
+        ident::string_literal(&entry.key),
+        entry.segments.iter().map(|s| ident::string_literal(s)).collect::<Vec<String>>().join(", "),
+        optional(entry.element.doc.as_deref()),
+        optional(entry.element.value_type.as_deref()),
+        optional(file.as_deref()),
+        origin.map_or("None".to_string(), |o| format!("Some({})", o.line)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_keys;
+
+    #[test]
+    fn every_key_is_found_in_its_slot() {
+        let input = (0..200).map(|i| format!("group{}.key{}", i % 7, i)).collect::<Vec<String>>().join("\n");
+        let compiled = compile_keys(&input, None, &Config::new(), &mut vec![]).unwrap();
+        let mut table = vec![];
+        for key in &compiled {
+            key.generate_code(&Config::new(), &[], ".", &[], &mut table).unwrap();
+        }
+
+        let (displacements, slots) = build(&table).unwrap();
+        assert_eq!(table.len(), slots.len());
+        for entry in &table {
+            let bucket = (hash(&entry.key, 0) % displacements.len() as u64) as usize;
+            let slot = (hash(&entry.key, u64::from(displacements[bucket])) % slots.len() as u64) as usize;
+            assert_eq!(entry.key, table[slots[slot]].key);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+### Messages of the example application
+
+-brand-name = Example
+
+# Greeting on the start page.
+welcome = Welcome to { -brand-name }!
+
+## Login screen
+
+login-input = Predefined value
+    .placeholder = email@example.com
+    .aria-label = Login input value
+
+emails =
+    { $unreadEmails ->
+        [one] You have one unread email.
+       *[other] You have { $unreadEmails } unread emails.
+    }